@@ -1,15 +1,25 @@
 use serde::{Deserialize, Serialize};
 use tokenizers::Tokenizer;
 use scraper::Node;
-use systema_client::Converter;
+use std::fmt::Debug;
+use systema_client::{Converter, DocumentNodes, DocumentState, EffectiveDate};
 use tracing::{error, info, warn};
 use utilites::Date;
-use crate::{error::{Error, Result}, context_model::ContextModel};
+use crate::{error::{Error, Result}, context_model::{ContextModel, ModelSettings}};
 
 pub struct Chunker
 {
     model: ContextModel,
 }
+///Настройки разбивки документа на чанки
+#[derive(Debug, Default, Clone)]
+pub struct ChunkOptions
+{
+    ///включать в чанки утратившие силу статьи и части (по умолчанию нет, они нужны только для вопросов по истории)
+    pub include_repealed: bool,
+    ///только узлы приложения с этим номером
+    pub appendix: Option<String>,
+}
 pub struct ChunkedText
 {
     pub content: String,
@@ -19,15 +29,69 @@ pub struct ChunkedText
 }
 impl Chunker
 {
+    ///Токенайзер и размер чанка модели из переменных окружения (`ModelSettings::from_env`)
     pub async fn new() -> Result<Self>
     {
-        let model = ContextModel::new(crate::context_model::ModelName::M3).await?;
+        Self::with_settings(ModelSettings::from_env()?).await
+    }
+    pub async fn with_settings(settings: ModelSettings) -> Result<Self>
+    {
+        let model = ContextModel::new(settings).await?;
         Ok(Self
         {
            model
         })
     }
     
+    ///Чанки по всем узлам документа, утратившие силу узлы (и их дочерние) пропускаются если не указано `include_repealed`
+    pub async fn chunk_document<C: ToString + Debug>(&self, document: &DocumentNodes<C>, options: &ChunkOptions) -> Result<Vec<Chunk>>
+    {
+        let mut chunks = Vec::with_capacity(document.node_count());
+        for node in document
+        {
+            let repealed = document.is_node_repealed(node);
+            if repealed && !options.include_repealed
+            {
+                continue;
+            }
+            let appendix = document.appendix_of(node).map(|a| a.number.clone());
+            if options.appendix.is_some() && options.appendix != appendix
+            {
+                continue;
+            }
+            let effective_date = document.effective_date_for(node).cloned();
+            let splitted = self.split_text(&node.text_with_notes()).await?;
+            for text in splitted
+            {
+                chunks.push(Chunk
+                {
+                    publication_url: document.publication_url().to_owned(),
+                    document_url: document.node_url(node),
+                    title: document.title().to_owned(),
+                    number: document.number().to_owned(),
+                    sign_date: document.sign_date().to_owned(),
+                    hash: document.hash().to_owned(),
+                    document_state: document.state(),
+                    path: document.find_all_parents_as_str(node),
+                    paragraph_start: node.start_id(),
+                    paragraph_end: node.end_id(),
+                    repealed,
+                    appendix: appendix.clone(),
+                    effective_date: effective_date.clone(),
+                    liks_hashes: node.links_hashes().cloned(),
+                    content: text.content,
+                    embeddings: None,
+                    meta: Some(ChunkMeta
+                    {
+                        chunk_index: text.chunk_index,
+                        token_count: text.token_count
+                    })
+                });
+            }
+        }
+        Ok(chunks)
+    }
+
     pub async fn split_text(&self, text: &str) -> Result<Vec<ChunkedText>> {
         // Токенизируем весь документ
         let encoding = self.model.tokenizer().encode(text, false)?;
@@ -460,7 +524,23 @@ pub struct Chunk
     pub number: String,
     pub sign_date: Date,
     pub hash: String,
+    ///правовое состояние документа, при поиске утратившие силу документы исключаются
+    pub document_state: DocumentState,
     pub path: String,
+    ///диапазон параграфов узла `p<id>` в тексте документа, `document_url` ведет на первый из них
+    #[serde(default)]
+    pub paragraph_start: usize,
+    #[serde(default)]
+    pub paragraph_end: usize,
+    ///узел утратил силу, такие чанки создаются только с `ChunkOptions::include_repealed`
+    #[serde(default)]
+    pub repealed: bool,
+    ///номер приложения, если узел из приложения к документу
+    #[serde(default)]
+    pub appendix: Option<String>,
+    ///когда вступает в силу норма из чанка, чтобы предупредить о еще не действующих положениях
+    #[serde(default)]
+    pub effective_date: Option<EffectiveDate>,
     pub content: String,
     pub liks_hashes: Option<Vec<String>>,
     pub embeddings: Option<Vec<f32>>,
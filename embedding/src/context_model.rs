@@ -1,31 +1,72 @@
-use std::{ops::Deref, sync::{LazyLock, OnceLock}};
-use crate::error::{Error, Result};
-use candle_core::Device;
-use candle_nn::VarBuilder;
-use candle_transformers::models::{bert::{BertModel, Config, DTYPE}};
+use std::{ops::Deref, path::PathBuf};
+use crate::{error::{Error, Result}, model_files::{Checksums, ModelFiles, ModelSource, Weights}, sparse::SparseHead};
+use candle_core::{Device, Tensor};
+use candle_nn::{Linear, Module};
+use candle_transformers::models::{bert::{self, BertModel, DTYPE}, xlm_roberta::{self, XLMRobertaModel}};
 use serde::{Deserialize, Serialize};
 use tokenizers::{PaddingParams, Tokenizer};
+use tokio::sync::OnceCell;
 use tracing::{error, info};
 
-static MODEL: OnceLock<BertModel> = OnceLock::<BertModel>::new();
+const DEFAULT_MODEL_DIR: &str = "./model";
 
-pub struct ContextModel
+///Модели эмбеддингов которые умеем загружать, у каждого семейства свое объединение токенов и свои префиксы
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ModelName
 {
-    //Каждый чанк будет представлен вектором из `1024` чисел
-    pub dimension: usize,
-    //чанкуем по `8192` токена
-    pub max_tokens: usize,
-    pub overlap_tokens: usize,
-    pub model_name: ModelName,
-    device: Device,
-    config: Config,
-    tokenizer: Tokenizer
+    ///`BAAI/bge-m3`, XLM-R, 8192 токена
+    M3,
+    ///`deepvk/USER-bge-m3`, bge-m3 дообученная на русском
+    UserBgeM3,
+    ///`intfloat/multilingual-e5-large`, XLM-R, 512 токенов, тексты с префиксами `query: ` и `passage: `
+    MultilingualE5,
+    ///`sentence-transformers/LaBSE`, BERT, 512 токенов, вектор текста из слоя pooler
+    LaBSE
 }
-
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-pub enum ModelName
+impl ModelName
 {
-    M3
+    ///Имя модели на hf (`BAAI/bge-m3`) или короткое имя (`bge-m3`, `user-bge-m3`, `e5`, `labse`)
+    pub fn from_name(name: &str) -> Option<Self>
+    {
+        let name = name.trim().to_lowercase();
+        let name = name.rsplit('/').next().unwrap_or_default();
+        match name
+        {
+            "bge-m3" | "m3" => Some(Self::M3),
+            "user-bge-m3" => Some(Self::UserBgeM3),
+            "multilingual-e5-large" | "multilingual-e5" | "e5" => Some(Self::MultilingualE5),
+            "labse" => Some(Self::LaBSE),
+            _ => None
+        }
+    }
+    ///Объединение токенов в вектор текста, как в карточке модели
+    pub fn pooling(&self) -> Pooling
+    {
+        match self
+        {
+            Self::M3 | Self::UserBgeM3 => Pooling::Cls,
+            Self::MultilingualE5 => Pooling::Mean,
+            Self::LaBSE => Pooling::Pooler
+        }
+    }
+    ///Префикс вопроса пользователя
+    pub fn query_prefix(&self) -> &'static str
+    {
+        match self
+        {
+            Self::MultilingualE5 => "query: ",
+            _ => ""
+        }
+    }
+    ///Префикс текста документа (чанка)
+    pub fn passage_prefix(&self) -> &'static str
+    {
+        match self
+        {
+            Self::MultilingualE5 => "passage: ",
+            _ => ""
+        }
+    }
 }
 impl Deref for ModelName
 {
@@ -34,7 +75,10 @@ impl Deref for ModelName
     {
         match self
         {
-            ModelName::M3 => "BAAI/bge-m3"
+            ModelName::M3 => "BAAI/bge-m3",
+            ModelName::UserBgeM3 => "deepvk/USER-bge-m3",
+            ModelName::MultilingualE5 => "intfloat/multilingual-e5-large",
+            ModelName::LaBSE => "sentence-transformers/LaBSE"
         }
     }
 }
@@ -46,16 +90,218 @@ impl AsRef<str> for ModelName
     }
 }
 
+///Объединение векторов токенов в вектор текста
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Pooling
+{
+    ///вектор первого токена (`[CLS]`, `<s>`)
+    Cls,
+    ///среднее по токенам без учета паддинга
+    Mean,
+    ///`[CLS]` через слой `pooler.dense` модели и tanh (`pooler_output` у BERT), так считает LaBSE
+    Pooler
+}
+impl Pooling
+{
+    fn from_name(name: &str) -> Option<Self>
+    {
+        match name.trim().to_lowercase().as_str()
+        {
+            "cls" => Some(Self::Cls),
+            "mean" => Some(Self::Mean),
+            "pooler" => Some(Self::Pooler),
+            _ => None
+        }
+    }
+    ///Объединение `[batch, tokens, hidden]` в `[batch, hidden]` с нормализацией L2
+    /// `pooler` - слой `pooler.dense` модели, нужен только для `Pooling::Pooler` (`Encoder::pooler`)
+    pub fn pool(&self, embeddings: &Tensor, attention_mask: &Tensor, pooler: Option<&Linear>) -> Result<Tensor>
+    {
+        let embeddings = match self
+        {
+            Self::Cls => embeddings.narrow(1, 0, 1)?.squeeze(1)?,
+            Self::Pooler =>
+            {
+                let pooler = pooler.ok_or(Error::ModelError("Для объединения pooler у модели нет слоя pooler.dense".to_owned()))?;
+                pooler.forward(&embeddings.narrow(1, 0, 1)?.squeeze(1)?)?.tanh()?
+            }
+            Self::Mean =>
+            {
+                let attention_mask_for_pooling = attention_mask.to_dtype(DTYPE)?.unsqueeze(2)?;
+                let sum_mask = attention_mask_for_pooling.sum(1)?;
+                let embeddings = (embeddings.broadcast_mul(&attention_mask_for_pooling)?).sum(1)?;
+                embeddings.broadcast_div(&sum_mask)?
+            }
+        };
+        Ok(embeddings.broadcast_div(&embeddings.sqr()?.sum_keepdim(1)?.sqrt()?)?)
+    }
+}
+
+///Где лежит модель и как ее использовать, чтобы сравнивать разные модели на одних и тех же документах
+#[derive(Debug, Clone)]
+pub struct ModelSettings
+{
+    pub name: ModelName,
+    ///каталог с `tokenizer.json`, `config.json` и весами модели
+    pub dir: PathBuf,
+    pub pooling: Pooling,
+    ///каталог кеша hf-hub, если модели нет в `dir`
+    pub cache_dir: Option<PathBuf>,
+    ///не скачивать модель, только `dir` и кеш hf-hub
+    pub offline: bool,
+    ///по умолчанию sha256 проверяется один раз, `Checksums::Always` пересчитывает при каждой загрузке
+    pub checksums: Checksums
+}
+impl Default for ModelSettings
+{
+    fn default() -> Self
+    {
+        Self::new(ModelName::M3)
+    }
+}
+impl ModelSettings
+{
+    ///Настройки модели по умолчанию для ее семейства, модель в `./model`
+    pub fn new(name: ModelName) -> Self
+    {
+        Self
+        {
+            name,
+            dir: PathBuf::from(DEFAULT_MODEL_DIR),
+            pooling: name.pooling(),
+            cache_dir: None,
+            offline: false,
+            checksums: Checksums::Once
+        }
+    }
+    ///Настройки переопределенные переменными окружения
+    /// `EMBEDDING_MODEL` (`bge-m3`, `user-bge-m3`, `e5`, `labse`), `EMBEDDING_MODEL_DIR`, `EMBEDDING_POOLING` (`cls`, `mean`, `pooler`),
+    /// `HF_HUB_CACHE`, `HF_HUB_OFFLINE`, `EMBEDDING_VERIFY_CHECKSUMS` (`once`, `always`, `skip`)
+    /// неизвестная модель или объединение это ошибка, иначе опечатка тихо даст векторы другой модели и размерности
+    pub fn from_env() -> Result<Self>
+    {
+        let env = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+        let flag = |key: &str| env(key).map(|v| v == "1" || v.eq_ignore_ascii_case("true"));
+        let name = match env("EMBEDDING_MODEL")
+        {
+            Some(name) => ModelName::from_name(&name)
+                .ok_or(Error::ModelError(["Неизвестная модель EMBEDDING_MODEL=", &name, ", поддерживаются bge-m3, user-bge-m3, e5, labse"].concat()))?,
+            None => ModelName::M3
+        };
+        let mut settings = Self::new(name);
+        if let Some(dir) = env("EMBEDDING_MODEL_DIR")
+        {
+            settings.dir = PathBuf::from(dir);
+        }
+        if let Some(pooling) = env("EMBEDDING_POOLING")
+        {
+            settings.pooling = Pooling::from_name(&pooling)
+                .ok_or(Error::ModelError(["Неизвестное объединение EMBEDDING_POOLING=", &pooling, ", поддерживаются cls, mean, pooler"].concat()))?;
+        }
+        settings.cache_dir = env("HF_HUB_CACHE").map(PathBuf::from);
+        settings.offline = flag("HF_HUB_OFFLINE").unwrap_or(false);
+        if let Some(checksums) = env("EMBEDDING_VERIFY_CHECKSUMS")
+        {
+            settings.checksums = Checksums::from_name(&checksums)
+                .ok_or(Error::ModelError(["Неизвестный режим EMBEDDING_VERIFY_CHECKSUMS=", &checksums, ", поддерживаются once, always, skip"].concat()))?;
+        }
+        Ok(settings)
+    }
+    pub fn with_dir(mut self, dir: impl Into<PathBuf>) -> Self
+    {
+        self.dir = dir.into();
+        self
+    }
+    pub fn with_pooling(mut self, pooling: Pooling) -> Self
+    {
+        self.pooling = pooling;
+        self
+    }
+    ///Только локальные файлы из `dir` или кеша hf-hub в `cache_dir`
+    pub fn with_offline_cache(mut self, cache_dir: impl Into<PathBuf>) -> Self
+    {
+        self.cache_dir = Some(cache_dir.into());
+        self.offline = true;
+        self
+    }
+    ///Где искать файлы модели
+    pub fn source(&self) -> ModelSource
+    {
+        ModelSource
+        {
+            repo_id: self.name.as_ref().to_owned(),
+            dir: Some(self.dir.clone()),
+            cache_dir: self.cache_dir.clone(),
+            offline: self.offline,
+            checksums: self.checksums
+        }
+    }
+}
+
+///Настройки архитектуры из config.json, по `model_type`
+#[derive(Debug, Clone)]
+pub enum EncoderConfig
+{
+    Bert(bert::Config),
+    XlmRoberta(xlm_roberta::Config)
+}
+
+///Загруженная модель
+pub enum Encoder
+{
+    ///со слоем `pooler.dense`, если он нужен для объединения токенов
+    Bert(BertModel, Option<Linear>),
+    XlmRoberta(XLMRobertaModel)
+}
+impl Encoder
+{
+    pub fn pooler(&self) -> Option<&Linear>
+    {
+        match self
+        {
+            Self::Bert(_, pooler) => pooler.as_ref(),
+            Self::XlmRoberta(_) => None
+        }
+    }
+    ///Векторы токенов `[batch, tokens, hidden]`
+    pub fn forward(&self, token_ids: &Tensor, token_type_ids: &Tensor, attention_mask: &Tensor) -> Result<Tensor>
+    {
+        let embeddings = match self
+        {
+            Self::Bert(model, _) => model.forward(token_ids, token_type_ids, Some(attention_mask))?,
+            Self::XlmRoberta(model) => model.forward(token_ids, attention_mask, token_type_ids, None, None, None)?
+        };
+        Ok(embeddings)
+    }
+}
+
+pub struct ContextModel
+{
+    ///размерность вектора (`hidden_size` из config.json)
+    pub dimension: usize,
+    ///сколько токенов модель принимает за раз, по этому размеру чанкуем
+    pub max_tokens: usize,
+    pub overlap_tokens: usize,
+    pub model_name: ModelName,
+    settings: ModelSettings,
+    device: Device,
+    config: EncoderConfig,
+    tokenizer: Tokenizer,
+    weights: Weights,
+    sparse_linear: Option<PathBuf>,
+    model: OnceCell<Encoder>,
+    sparse: OnceCell<SparseHead>
+}
+
 impl ContextModel 
 {
-    pub async fn new(model_name: ModelName) -> Result<Self>
+    pub async fn new(settings: ModelSettings) -> Result<Self>
     {
-        info!("Попытка загрузить токенайзер");
+        info!("Попытка загрузить токенайзер {} из {}", settings.name.as_ref(), settings.dir.display());
+        let files = ModelFiles::resolve(&settings.source()).await?;
         let device = Device::cuda_if_available(0).unwrap_or(Device::Cpu);
         println!("Device: {:?}", &device);
-        let max_tokens = 8192;
-        let dimension = 1024;
-        let tokenizer = tokio::fs::read("./model/tokenizer.json").await?;
+        let tokenizer = tokio::fs::read(&files.tokenizer).await?;
         let mut tokenizer = Tokenizer::from_bytes(tokenizer)?;
         let pp = PaddingParams 
         {
@@ -63,43 +309,75 @@ impl ContextModel
             ..Default::default()
         };
         tokenizer.with_padding(Some(pp));
-        let config = tokio::fs::read_to_string("./model/config.json").await?;
-        let config = serde_json::from_str(&config)?;
-        // let padding = PaddingParams 
-        // {
-        //     strategy: tokenizers::PaddingStrategy::Fixed(max_tokens),
-        //     direction: tokenizers::PaddingDirection::Right,
-        //     pad_to_multiple_of: None,
-        //     pad_id: 0,
-        //     pad_type_id: 0,
-        //     pad_token: "[PAD]".to_string(),
-        // };
-        // tokenizer.with_padding(Some(padding));
+        let config = tokio::fs::read_to_string(&files.config).await?;
+        let config: serde_json::Value = serde_json::from_str(&config)?;
+        let setting = |key: &str| config.get(key).and_then(|v| v.as_u64()).map(|v| v as usize)
+            .ok_or(Error::ContentError(["В config.json модели нет `", key, "`"].concat()));
+        let dimension = setting("hidden_size")?;
+        let max_positions = setting("max_position_embeddings")?;
+        let model_type = config.get("model_type").and_then(|t| t.as_str()).map(|t| t.to_owned());
+        let (config, max_tokens) = match model_type.as_deref()
+        {
+            //у XLM-R позиции начинаются после `pad_token_id`, поэтому токенов на 2 меньше чем позиций (8194 -> 8192)
+            Some("xlm-roberta") =>
+            {
+                let pad = setting("pad_token_id").unwrap_or(1);
+                (EncoderConfig::XlmRoberta(serde_json::from_value(config)?), max_positions.saturating_sub(pad + 1))
+            }
+            _ => (EncoderConfig::Bert(serde_json::from_value(config)?), max_positions)
+        };
+        info!("модель {}: размерность {}, токенов {}, объединение {:?}", settings.name.as_ref(), dimension, max_tokens, settings.pooling);
         Ok(Self
         {
             dimension,
             max_tokens,
             overlap_tokens: (max_tokens / 10).min(256), // Максимум 256 токенов перекрытия,
-            model_name: model_name,
+            model_name: settings.name,
+            settings,
             config,
             device,
-            tokenizer
+            tokenizer,
+            weights: files.weights,
+            sparse_linear: files.sparse_linear,
+            model: OnceCell::new(),
+            sparse: OnceCell::new()
         })
     }
-    async fn load_model(&self) -> Result<BertModel>
+    async fn load_model(&self) -> Result<Encoder>
     {
         let start = std::time::Instant::now();
         let config = self.config().clone();
         let model_name = self.model_name.as_ref().to_owned();
         let device = self.device().clone();
+        let weights = self.weights.clone();
+        let pooling = self.settings.pooling;
+        let hidden_size = self.dimension;
         let result = tokio::task::spawn_blocking(move ||
         {
-            let vb = VarBuilder::from_pth("./model/pytorch_model.bin", DTYPE, &device)?;
-            // Use tanh based approximation for Gelu instead of erf implementation.
-            // if self.approximate_gelu {
-            //     config.hidden_act = HiddenAct::GeluApproximate;
-            // }
-            let model = BertModel::load(vb, &config)?;
+            let vb = weights.var_builder(DTYPE, &device)?;
+            let model = match &config
+            {
+                EncoderConfig::Bert(config) =>
+                {
+                    let pooler = if pooling == Pooling::Pooler
+                    {
+                        //в весах `pooler.dense`, у некоторых моделей с префиксом `bert.`
+                        let pooler = candle_nn::linear(hidden_size, hidden_size, vb.pp("pooler.dense"))
+                            .or_else(|_| candle_nn::linear(hidden_size, hidden_size, vb.pp("bert.pooler.dense")))?;
+                        Some(pooler)
+                    }
+                    else
+                    {
+                        None
+                    };
+                    Encoder::Bert(BertModel::load(vb, config)?, pooler)
+                }
+                EncoderConfig::XlmRoberta(_) if pooling == Pooling::Pooler =>
+                {
+                    return Err(Error::ModelError(["Объединение pooler поддерживается только для BERT, модель ", &model_name].concat()));
+                }
+                EncoderConfig::XlmRoberta(config) => Encoder::XlmRoberta(XLMRobertaModel::new(config, vb)?)
+            };
             info!("model {} loaded in {:?}", model_name, start.elapsed());
             Ok(model)
         }).await?;
@@ -117,29 +395,40 @@ impl ContextModel
     {
         &self.device
     }
-    pub fn config(&self) -> &Config
+    pub fn config(&self) -> &EncoderConfig
     {
         &self.config
     }
-    pub async fn model(&self) -> Result<&BertModel>
+    pub fn settings(&self) -> &ModelSettings
     {
-        if let Some(model) = MODEL.get()
-        {
-            Ok(model)
-        }
-        else 
+        &self.settings
+    }
+    ///Модель загружается при первом обращении
+    pub async fn model(&self) -> Result<&Encoder>
+    {
+        self.model.get_or_try_init(|| self.load_model()).await.inspect_err(|e| error!("{}", e))
+    }
+    ///Слой лексических весов, есть только у BGE-M3 (`sparse_linear.pt`)
+    pub async fn sparse_head(&self) -> Result<&SparseHead>
+    {
+        self.sparse.get_or_try_init(|| async
         {
-            let model = self.load_model().await.inspect_err(|e| error!("{}", e))?;
-            let _ = MODEL.set(model);
-            Ok(MODEL.get().unwrap())
-        }
+            let file = self.sparse_linear.clone()
+                .ok_or(Error::ModelError(["У модели ", self.model_name.as_ref(), " нет sparse_linear.pt, лексические веса недоступны"].concat()))?;
+            let special_ids = self.tokenizer.get_added_tokens_decoder().into_iter()
+                .filter(|(_, token)| token.special)
+                .map(|(id, _)| id)
+                .collect();
+            let dimension = self.dimension;
+            let device = self.device.clone();
+            tokio::task::spawn_blocking(move || SparseHead::load(&file, dimension, special_ids, &device)).await?
+        }).await.inspect_err(|e| error!("{}", e))
     }
 }
 
 #[cfg(test)]
 mod tests
 {
-    use candle_transformers::models::bert::DTYPE;
     use candle_core::Tensor;
     use tokenizers::PaddingParams;
     use tracing::info;
@@ -147,12 +436,46 @@ mod tests
     use crate::context_model::ContextModel;
     use crate::error::{Error, Result};
     use crate::logger;
+    #[test]
+    fn test_model_settings()
+    {
+        use super::{ModelName, Pooling};
+        assert_eq!(ModelName::from_name("intfloat/multilingual-e5-large"), Some(ModelName::MultilingualE5));
+        assert_eq!(ModelName::from_name("USER-bge-m3"), Some(ModelName::UserBgeM3));
+        assert_eq!(ModelName::from_name("bge-m3"), Some(ModelName::M3));
+        assert!(ModelName::from_name("gpt").is_none());
+        assert_eq!(ModelName::MultilingualE5.query_prefix(), "query: ");
+        let settings = super::ModelSettings::new(ModelName::MultilingualE5).with_dir("/models/e5");
+        assert_eq!(settings.pooling, Pooling::Mean);
+        assert_eq!(settings.dir.join("config.json").to_str(), Some("/models/e5/config.json"));
+        let source = settings.with_offline_cache("/models/hub").source();
+        assert_eq!(source.repo_id, "intfloat/multilingual-e5-large");
+        assert!(source.offline && source.checksums == crate::Checksums::Once);
+        let device = candle_core::Device::Cpu;
+        //[batch 1, 2 токена, 2]
+        let embeddings = Tensor::new(&[[[3f32, 4.], [0., 1.]]], &device).unwrap();
+        let mask = Tensor::new(&[[1u32, 1]], &device).unwrap();
+        let cls: Vec<Vec<f32>> = Pooling::Cls.pool(&embeddings, &mask, None).unwrap().to_vec2().unwrap();
+        assert_eq!(cls, vec![vec![0.6, 0.8]]);
+        let mask = Tensor::new(&[[1u32, 0]], &device).unwrap();
+        let mean: Vec<Vec<f32>> = Pooling::Mean.pool(&embeddings, &mask, None).unwrap().to_vec2().unwrap();
+        assert_eq!(mean, vec![vec![0.6, 0.8]]);
+        //pooler: tanh(W·cls + b), W - единичная, b = 0
+        assert!(Pooling::Pooler.pool(&embeddings, &mask, None).is_err());
+        let pooler = candle_nn::Linear::new(Tensor::eye(2, candle_core::DType::F32, &device).unwrap(), Some(Tensor::zeros(2, candle_core::DType::F32, &device).unwrap()));
+        let pooled: Vec<Vec<f32>> = Pooling::Pooler.pool(&embeddings, &mask, Some(&pooler)).unwrap().to_vec2().unwrap();
+        let expected = [3f32.tanh(), 4f32.tanh()];
+        let norm = (expected[0] * expected[0] + expected[1] * expected[1]).sqrt();
+        assert!((pooled[0][0] - expected[0] / norm).abs() < 1e-6 && (pooled[0][1] - expected[1] / norm).abs() < 1e-6);
+        assert_eq!(ModelName::LaBSE.pooling(), Pooling::Pooler);
+    }
+
     #[tokio::test]
     async fn test_model()
     {
         logger::init();
         let start = std::time::Instant::now();
-        let mut context = super::ContextModel::new(crate::context_model::ModelName::M3).await.unwrap();
+        let mut context = super::ContextModel::new(super::ModelSettings::from_env().unwrap()).await.unwrap();
         let sentences = [
             "Документы (сведения), не содержащие налоговую тайну, используемые налоговыми органами при реализации своих полномочий в отношениях, регулируемых законодательством о налогах и сборах, передаются налоговым органом налогоплательщику - физическому лицу, зарегистрированному в единой системе идентификации и аутентификации, в электронной форме через личный кабинет на едином портале государственных и муниципальных услуг, если такой порядок передачи документов (сведений), не содержащих налоговую тайну, предусмотрен настоящим Кодексом или если указанные документы (сведения) включены в перечень, утверждаемый в соответствии с абзацем первым пункта 9 настоящей статьи",
             "Что происходит со сведениями не содержащими налоговую тайну?",
@@ -200,9 +523,10 @@ mod tests
         let attention_mask = Tensor::stack(&attention_mask, 0).unwrap();
         let token_type_ids = token_ids.zeros_like().unwrap();
         info!("running inference on batch {:?}", token_ids.shape());
-        let embeddings = context.model().await.unwrap().forward(&token_ids, &token_type_ids, Some(&attention_mask)).unwrap();
+        let model = context.model().await.unwrap();
+        let embeddings = model.forward(&token_ids, &token_type_ids, &attention_mask).unwrap();
         info!("generated embeddings {:?}", embeddings.shape());
-        let embeddings = mean_pooling(&embeddings, &attention_mask).unwrap();
+        let embeddings = context.settings().pooling.pool(&embeddings, &attention_mask, model.pooler()).unwrap();
         //let embeddings = normalize_l2(&embeddings).unwrap();
         info!("pooled embeddings {:?}", embeddings.shape());
         //let vec: Vec<f32> = embeddings.to_vec1().unwrap();
@@ -243,14 +567,4 @@ mod tests
     {
         Ok(v.broadcast_div(&v.sqr().unwrap().sum_keepdim(1).unwrap().sqrt().unwrap()).unwrap())
     }
-
-    fn mean_pooling(embeddings: &Tensor, attention_mask: &Tensor) -> Result<Tensor> 
-    {
-        let attention_mask_for_pooling = attention_mask.to_dtype(DTYPE)?.unsqueeze(2)?;
-        let sum_mask = attention_mask_for_pooling.sum(1)?;
-        let embeddings = (embeddings.broadcast_mul(&attention_mask_for_pooling)?).sum(1)?;
-        let result = embeddings.broadcast_div(&sum_mask)?;
-        let result = result.broadcast_div(&result.sqr()?.sum_keepdim(1)?.sqrt()?)?;
-        Ok(result)
-    }
 }
\ No newline at end of file
@@ -1,10 +1,10 @@
-use candle_transformers::models::bert::{BertModel, Config, HiddenAct, DTYPE};
+use candle_transformers::models::bert::{BertModel, Config, HiddenAct};
 use candle_core::Tensor;
 use candle_nn::VarBuilder;
 use hf_hub::{api::sync::Api, Repo, RepoType};
 use tokenizers::{PaddingParams, Tokenizer};
 use tracing::info;
-use crate::{context_model::ContextModel, error::{Error, Result}};
+use crate::{context_model::{ContextModel, ModelSettings}, error::{Error, Result}, sparse::HybridEmbedding};
 
 pub struct Embeddings
 {
@@ -12,13 +12,37 @@ pub struct Embeddings
 }
 impl Embeddings
 {
+    ///Модель из переменных окружения (`ModelSettings::from_env`)
     pub async fn new() -> Result<Self>
+    {
+        Self::with_settings(ModelSettings::from_env()?).await
+    }
+    pub async fn with_settings(settings: ModelSettings) -> Result<Self>
     {
         Ok(Self
         {
-            context_model: ContextModel::new(crate::context_model::ModelName::M3).await?
+            context_model: ContextModel::new(settings).await?
         })
     }
+    ///Векторы вопросов пользователя, с префиксом вопроса модели (`query: ` у e5)
+    pub async fn embed_queries(&self, texts: &[&str]) -> Result<Tensor>
+    {
+        let prefix = self.context_model.model_name.query_prefix();
+        let texts: Vec<String> = texts.iter().map(|t| [prefix, t].concat()).collect();
+        self.embed_tensor_batch(&texts.iter().map(|t| t.as_str()).collect::<Vec<&str>>()).await
+    }
+    ///Векторы чанков документов, с префиксом текста модели (`passage: ` у e5)
+    pub async fn embed_passages(&self, texts: &[&str]) -> Result<Tensor>
+    {
+        let prefix = self.context_model.model_name.passage_prefix();
+        let texts: Vec<String> = texts.iter().map(|t| [prefix, t].concat()).collect();
+        self.embed_tensor_batch(&texts.iter().map(|t| t.as_str()).collect::<Vec<&str>>()).await
+    }
+    ///Размерность векторов модели
+    pub fn dimension(&self) -> usize
+    {
+        self.context_model.dimension
+    }
     async fn embed_vec(&self, text: &str) -> Result<Vec<f32>>
     {
         let embeddings = self.embed_tensor(text).await?;
@@ -33,6 +57,27 @@ impl Embeddings
     pub async fn embed_tensor_batch(&self, texts: &[&str]) -> Result<Tensor>
     {
         let start = std::time::Instant::now();
+        let (embeddings, _, attention_mask) = self.forward_batch(texts).await?;
+        let pooler = self.context_model.model().await?.pooler();
+        let embeddings = self.context_model.settings().pooling.pool(&embeddings, &attention_mask, pooler)?;
+        info!("pooled embeddings {:?} in {:?} s", embeddings.shape(), start.elapsed());
+        Ok(embeddings)
+    }
+    ///Плотные векторы и лексические веса токенов (только BGE-M3) за один проход модели
+    pub async fn embed_hybrid(&self, texts: &[&str]) -> Result<Vec<HybridEmbedding>>
+    {
+        let start = std::time::Instant::now();
+        let sparse_head = self.context_model.sparse_head().await?;
+        let (embeddings, token_ids, attention_mask) = self.forward_batch(texts).await?;
+        let sparse = sparse_head.forward(&embeddings, &token_ids, &attention_mask)?;
+        let pooler = self.context_model.model().await?.pooler();
+        let dense: Vec<Vec<f32>> = self.context_model.settings().pooling.pool(&embeddings, &attention_mask, pooler)?.to_vec2()?;
+        info!("hybrid embeddings {} in {:?} s", dense.len(), start.elapsed());
+        Ok(dense.into_iter().zip(sparse).map(|(dense, sparse)| HybridEmbedding { dense, sparse }).collect())
+    }
+    ///Векторы токенов последнего слоя `[batch, tokens, hidden]`, id токенов и маска
+    async fn forward_batch(&self, texts: &[&str]) -> Result<(Tensor, Tensor, Tensor)>
+    {
         let device = self.context_model.device();
         let model = self.context_model.model().await?;
          let tokens = self.context_model.tokenizer()
@@ -60,21 +105,9 @@ impl Embeddings
         let attention_mask = Tensor::stack(&attention_mask, 0)?;
         let token_type_ids = token_ids.zeros_like()?;
         info!("running inference {:?}", token_ids.shape());
-        let embeddings = model.forward(&token_ids, &token_type_ids, Some(&attention_mask))?;
+        let embeddings = model.forward(&token_ids, &token_type_ids, &attention_mask)?;
         info!("generated embeddings {:?}", embeddings.shape());
-        let embeddings = Self::mean_pooling(&embeddings, &attention_mask)?;
-        info!("pooled embeddings {:?} in {:?} s", embeddings.shape(), start.elapsed());
-        Ok(embeddings)
-    }
-
-    fn mean_pooling(embeddings: &Tensor, attention_mask: &Tensor) -> Result<Tensor> 
-    {
-        let attention_mask_for_pooling = attention_mask.to_dtype(DTYPE)?.unsqueeze(2)?;
-        let sum_mask = attention_mask_for_pooling.sum(1)?;
-        let embeddings = (embeddings.broadcast_mul(&attention_mask_for_pooling)?).sum(1)?;
-        let embeddings = embeddings.broadcast_div(&sum_mask)?;
-        let embeddings = embeddings.broadcast_div(&embeddings.sqr()?.sum_keepdim(1)?.sqrt()?)?;
-        Ok(embeddings)
+        Ok((embeddings, token_ids, attention_mask))
     }
 
     async fn embed_tensor(&self, text: &str) -> Result<Tensor>
@@ -88,9 +121,9 @@ impl Embeddings
         let attention_mask = Tensor::new(tokens.get_attention_mask(), self.context_model.device())?.unsqueeze(0)?;
         let token_type_ids = token_ids.zeros_like()?;
         info!("running inference {:?}", token_ids.shape());
-        let embeddings = model.forward(&token_ids, &token_type_ids, Some(&attention_mask))?;
+        let embeddings = model.forward(&token_ids, &token_type_ids, &attention_mask)?;
         info!("generated embeddings {:?}", embeddings.shape());
-        let embeddings = Self::mean_pooling(&embeddings, &attention_mask)?;
+        let embeddings = self.context_model.settings().pooling.pool(&embeddings, &attention_mask, model.pooler())?;
         info!("pooled embeddings {:?} in {:?} s", embeddings.shape(), start.elapsed());
         Ok(embeddings)
     }
@@ -12,6 +12,14 @@ pub enum Error
     ContentError(String),
     #[error(transparent)]
     UtilitesError(#[from] utilites::error::Error),
+    #[error("Model error: `{0}`")]
+    ModelError(String),
+    #[error(transparent)]
+    HubError
+    {
+        #[from]
+        source: hf_hub::api::tokio::ApiError
+    },
     #[error("parse html error: `{0}`")]
     ScraperError(String),
     #[error(transparent)]
@@ -0,0 +1,384 @@
+use std::{collections::BTreeSet, path::{Path, PathBuf}};
+use candle_core::{DType, Device};
+use candle_nn::VarBuilder;
+use hf_hub::{Cache, api::tokio::ApiBuilder};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+use crate::error::{Error, Result};
+
+const TOKENIZER: &str = "tokenizer.json";
+const CONFIG: &str = "config.json";
+const SAFETENSORS: &str = "model.safetensors";
+const SAFETENSORS_INDEX: &str = "model.safetensors.index.json";
+const PTH: &str = "pytorch_model.bin";
+///лексические веса токенов BGE-M3, у других моделей файла нет
+const SPARSE_LINEAR: &str = "sparse_linear.pt";
+///векторы токенов ColBERT у BGE-M3
+const COLBERT_LINEAR: &str = "colbert_linear.pt";
+///контрольные суммы файлов в каталоге модели, формат `sha256sum`: `<sha256>  <файл>`
+const CHECKSUMS: &str = "checksums.sha256";
+///рядом с проверенным файлом: `<sha256> <размер> <время изменения>`
+const VERIFIED_SUFFIX: &str = ".verified";
+
+///Когда проверять sha256 файлов модели
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Checksums
+{
+    ///не проверять
+    Skip,
+    ///один раз, после скачивания или при первой загрузке, проверенная сумма запоминается рядом с файлом
+    /// и пока размер и время изменения файла те же, файл заново не читается
+    Once,
+    ///при каждой загрузке, файлы весов читаются целиком
+    Always
+}
+impl Checksums
+{
+    pub(crate) fn from_name(name: &str) -> Option<Self>
+    {
+        match name.trim().to_lowercase().as_str()
+        {
+            "skip" | "0" | "false" => Some(Self::Skip),
+            "once" | "1" | "true" => Some(Self::Once),
+            "always" => Some(Self::Always),
+            _ => None
+        }
+    }
+}
+
+///Откуда брать файлы модели
+#[derive(Debug, Clone)]
+pub struct ModelSource
+{
+    ///репозиторий модели на hf `BAAI/bge-m3`
+    pub repo_id: String,
+    ///локальный каталог с файлами модели, проверяется первым
+    pub dir: Option<PathBuf>,
+    ///каталог кеша hf-hub, по умолчанию из `HF_HOME` (`~/.cache/huggingface/hub`)
+    pub cache_dir: Option<PathBuf>,
+    ///только локальный каталог и кеш, ничего не скачиваем
+    pub offline: bool,
+    ///когда проверять sha256 файлов (в кеше hf-hub файлы весов названы своей sha256)
+    pub checksums: Checksums
+}
+impl ModelSource
+{
+    ///Сначала каталог, потом кеш hf-hub по умолчанию, при необходимости скачиваем, контрольные суммы проверяются один раз
+    pub fn new(repo_id: &str) -> Self
+    {
+        Self
+        {
+            repo_id: repo_id.to_owned(),
+            dir: None,
+            cache_dir: None,
+            offline: false,
+            checksums: Checksums::Once
+        }
+    }
+    pub fn with_dir(mut self, dir: impl Into<PathBuf>) -> Self
+    {
+        self.dir = Some(dir.into());
+        self
+    }
+    fn cache(&self) -> Cache
+    {
+        match &self.cache_dir
+        {
+            Some(dir) => Cache::new(dir.clone()),
+            None => Cache::from_env()
+        }
+    }
+}
+
+///Веса модели
+#[derive(Debug, Clone)]
+pub enum Weights
+{
+    ///один или несколько (`model-00001-of-00002.safetensors`) файлов, читаются через mmap
+    Safetensors(Vec<PathBuf>),
+    ///`pytorch_model.bin`, читается целиком, только если safetensors нет
+    Pth(PathBuf)
+}
+impl Weights
+{
+    pub fn var_builder(&self, dtype: DType, device: &Device) -> Result<VarBuilder<'static>>
+    {
+        let vb = match self
+        {
+            //файлы не должны меняться пока модель загружена, поэтому mmap небезопасен
+            Self::Safetensors(files) => unsafe { VarBuilder::from_mmaped_safetensors(files, dtype, device)? },
+            Self::Pth(file) =>
+            {
+                warn!("safetensors для модели нет, веса загружаются из {}", file.display());
+                VarBuilder::from_pth(file, dtype, device)?
+            }
+        };
+        Ok(vb)
+    }
+    fn files(&self) -> Vec<&Path>
+    {
+        match self
+        {
+            Self::Safetensors(files) => files.iter().map(|f| f.as_path()).collect(),
+            Self::Pth(file) => vec![file.as_path()]
+        }
+    }
+}
+
+///Найденные файлы модели
+#[derive(Debug, Clone)]
+pub struct ModelFiles
+{
+    pub tokenizer: PathBuf,
+    pub config: PathBuf,
+    pub weights: Weights,
+    pub sparse_linear: Option<PathBuf>,
+    pub colbert_linear: Option<PathBuf>
+}
+impl ModelFiles
+{
+    ///Файлы из локального каталога или кеша hf-hub, если их нет и не `offline` - скачиваются в кеш
+    pub async fn resolve(source: &ModelSource) -> Result<Self>
+    {
+        //проверка контрольных сумм читает файлы весов целиком
+        let local = source.clone();
+        if let Some(files) = tokio::task::spawn_blocking(move || Self::resolve_local(&local)).await??
+        {
+            return Ok(files);
+        }
+        if source.offline
+        {
+            return Err(Error::ModelError(["Модель ", &source.repo_id, " не найдена ни в каталоге модели ни в кеше hf-hub, а скачивание отключено (offline)"].concat()));
+        }
+        info!("скачиваем модель {} в кеш hf-hub", source.repo_id);
+        let api = ApiBuilder::from_cache(source.cache()).with_progress(false).build()?;
+        let repo = api.model(source.repo_id.clone());
+        repo.get(TOKENIZER).await?;
+        repo.get(CONFIG).await?;
+        if repo.get(SAFETENSORS).await.is_err()
+        {
+            match repo.get(SAFETENSORS_INDEX).await
+            {
+                Ok(index) =>
+                {
+                    for shard in shards(&index)?
+                    {
+                        repo.get(&shard).await?;
+                    }
+                }
+                Err(_) => { repo.get(PTH).await?; }
+            }
+        }
+        for head in [SPARSE_LINEAR, COLBERT_LINEAR]
+        {
+            if repo.get(head).await.is_err()
+            {
+                info!("у модели {} нет {}", source.repo_id, head);
+            }
+        }
+        let local = source.clone();
+        tokio::task::spawn_blocking(move || Self::resolve_local(&local)).await??
+            .ok_or(Error::ModelError(["Модель ", &source.repo_id, " скачана, но в кеше hf-hub ее файлов нет"].concat()))
+    }
+    ///Только локальный каталог и кеш hf-hub, без сети
+    pub fn resolve_local(source: &ModelSource) -> Result<Option<Self>>
+    {
+        if let Some(dir) = &source.dir
+            && let Some(files) = Self::find(|name| Some(dir.join(name)).filter(|p| p.exists()))?
+        {
+            info!("модель {} из каталога {}", source.repo_id, dir.display());
+            if source.checksums != Checksums::Skip
+            {
+                verify_dir(dir, &files, source.checksums)?;
+            }
+            return Ok(Some(files));
+        }
+        let repo = source.cache().model(source.repo_id.clone());
+        if let Some(files) = Self::find(|name| repo.get(name))?
+        {
+            info!("модель {} из кеша hf-hub", source.repo_id);
+            if source.checksums != Checksums::Skip
+            {
+                verify_cache(&files, source.checksums)?;
+            }
+            return Ok(Some(files));
+        }
+        Ok(None)
+    }
+    ///Набор файлов модели, safetensors предпочтительнее `pytorch_model.bin`
+    fn find(get: impl Fn(&str) -> Option<PathBuf>) -> Result<Option<Self>>
+    {
+        let (Some(tokenizer), Some(config)) = (get(TOKENIZER), get(CONFIG)) else
+        {
+            return Ok(None);
+        };
+        let weights = if let Some(file) = get(SAFETENSORS)
+        {
+            Weights::Safetensors(vec![file])
+        }
+        else if let Some(index) = get(SAFETENSORS_INDEX)
+        {
+            let files: Option<Vec<PathBuf>> = shards(&index)?.iter().map(|s| get(s)).collect();
+            match files
+            {
+                Some(files) => Weights::Safetensors(files),
+                None => return Ok(None)
+            }
+        }
+        else if let Some(file) = get(PTH)
+        {
+            Weights::Pth(file)
+        }
+        else
+        {
+            return Ok(None);
+        };
+        Ok(Some(Self { tokenizer, config, weights, sparse_linear: get(SPARSE_LINEAR), colbert_linear: get(COLBERT_LINEAR) }))
+    }
+}
+
+///Имена файлов из `model.safetensors.index.json`
+fn shards(index: &Path) -> Result<BTreeSet<String>>
+{
+    let index: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(index)?)?;
+    let shards = index.get("weight_map").and_then(|m| m.as_object())
+        .map(|m| m.values().filter_map(|v| v.as_str().map(|s| s.to_owned())).collect())
+        .unwrap_or_default();
+    Ok(shards)
+}
+
+pub(crate) fn sha256(file: &Path) -> Result<String>
+{
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut std::fs::File::open(file)?, &mut hasher)?;
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+///Отметка о проверке файла, меняется вместе с ожидаемой суммой, размером или временем изменения файла
+fn stamp(file: &Path, expected: &str) -> Result<String>
+{
+    let metadata = std::fs::metadata(file)?;
+    let modified = metadata.modified()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    Ok([&expected.to_lowercase(), " ", &metadata.len().to_string(), " ", &modified.to_string()].concat())
+}
+fn verified_path(file: &Path) -> PathBuf
+{
+    let mut name = file.file_name().unwrap_or_default().to_owned();
+    name.push(VERIFIED_SUFFIX);
+    file.with_file_name(name)
+}
+
+fn check(file: &Path, expected: &str, checksums: Checksums) -> Result<()>
+{
+    let stamp = stamp(file, expected)?;
+    let verified = verified_path(file);
+    if checksums == Checksums::Once && std::fs::read_to_string(&verified).is_ok_and(|v| v == stamp)
+    {
+        return Ok(());
+    }
+    let start = std::time::Instant::now();
+    let actual = sha256(file)?;
+    if !actual.eq_ignore_ascii_case(expected)
+    {
+        return Err(Error::ModelError(["Контрольная сумма ", &file.display().to_string(), " ", &actual, " не совпадает с ожидаемой ", expected].concat()));
+    }
+    info!("контрольная сумма {} проверена за {:?}", file.display(), start.elapsed());
+    //кеш может быть смонтирован только для чтения, тогда в следующий раз проверим снова
+    if let Err(e) = std::fs::write(&verified, stamp)
+    {
+        warn!("не удалось запомнить проверку {}: {}", verified.display(), e);
+    }
+    Ok(())
+}
+
+///Проверка по `checksums.sha256` из каталога модели, если файла нет - проверять не с чем
+fn verify_dir(dir: &Path, files: &ModelFiles, checksums: Checksums) -> Result<()>
+{
+    let checksums = dir.join(CHECKSUMS);
+    if !checksums.exists()
+    {
+        warn!("в каталоге модели {} нет {}, контрольные суммы не проверяются", dir.display(), CHECKSUMS);
+        return Ok(());
+    }
+    let checksums = std::fs::read_to_string(checksums)?;
+    let expected = |file: &Path| checksums.lines()
+        .filter_map(|l| l.split_once(char::is_whitespace))
+        .find(|(_, name)| Path::new(name.trim().trim_start_matches('*')).file_name() == file.file_name())
+        .map(|(hash, _)| hash.to_owned());
+    for file in files.weights.files().into_iter()
+        .chain([files.tokenizer.as_path(), files.config.as_path()])
+        .chain(files.sparse_linear.as_deref())
+        .chain(files.colbert_linear.as_deref())
+    {
+        match expected(file)
+        {
+            Some(hash) => check(file, &hash, checksums)?,
+            None => warn!("для {} нет контрольной суммы в {}", file.display(), CHECKSUMS)
+        }
+    }
+    Ok(())
+}
+
+///В кеше hf-hub файлы это ссылки на `blobs/<etag>`, у файлов весов (LFS) etag это их sha256
+fn verify_cache(files: &ModelFiles, checksums: Checksums) -> Result<()>
+{
+    for file in files.weights.files()
+    {
+        let etag = std::fs::read_link(file).ok()
+            .and_then(|target| target.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .filter(|etag| etag.len() == 64 && etag.chars().all(|c| c.is_ascii_hexdigit()));
+        match etag
+        {
+            Some(etag) => check(file, &etag, checksums)?,
+            None => warn!("для {} в кеше hf-hub нет sha256, контрольная сумма не проверяется", file.display())
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::{Checksums, ModelFiles, ModelSource, Weights};
+
+    #[test]
+    fn test_resolve_dir()
+    {
+        let dir = std::env::temp_dir().join(["embedding_model_files_test_", &std::process::id().to_string()].concat());
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in ["tokenizer.json", "config.json", "pytorch_model.bin", "model.safetensors"]
+        {
+            std::fs::write(dir.join(file), file).unwrap();
+        }
+        let mut source = ModelSource { repo_id: "test/model".to_owned(), dir: Some(dir.clone()), cache_dir: Some(dir.join("cache")), offline: true, checksums: Checksums::Once };
+        let files = ModelFiles::resolve_local(&source).unwrap().unwrap();
+        assert!(matches!(files.weights, Weights::Safetensors(ref f) if f[0].ends_with("model.safetensors")));
+        let checksum = super::sha256(&dir.join("model.safetensors")).unwrap();
+        std::fs::write(dir.join("checksums.sha256"), [&checksum, "  model.safetensors\n"].concat()).unwrap();
+        assert!(ModelFiles::resolve_local(&source).is_ok());
+        let verified = dir.join("model.safetensors.verified");
+        assert!(verified.exists());
+        let wrong = "0".repeat(64);
+        std::fs::write(dir.join("checksums.sha256"), [wrong.as_str(), "  model.safetensors\n"].concat()).unwrap();
+        assert!(ModelFiles::resolve_local(&source).is_err());
+        //отметка о проверке с этой суммой есть: Once файл не читает, Always пересчитывает
+        std::fs::write(&verified, super::stamp(&dir.join("model.safetensors"), &wrong).unwrap()).unwrap();
+        assert!(ModelFiles::resolve_local(&source).is_ok());
+        source.checksums = Checksums::Always;
+        assert!(ModelFiles::resolve_local(&source).is_err());
+        source.checksums = Checksums::Skip;
+        assert!(ModelFiles::resolve_local(&source).is_ok());
+        std::fs::remove_file(dir.join("model.safetensors")).unwrap();
+        std::fs::remove_file(dir.join("checksums.sha256")).unwrap();
+        let files = ModelFiles::resolve_local(&source).unwrap().unwrap();
+        assert!(matches!(files.weights, Weights::Pth(_)));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(ModelFiles::resolve_local(&source).unwrap().is_none());
+        assert_eq!(Checksums::from_name("always"), Some(Checksums::Always));
+        assert_eq!(Checksums::from_name("false"), Some(Checksums::Skip));
+        assert!(Checksums::from_name("sometimes").is_none());
+    }
+}
@@ -0,0 +1,110 @@
+use std::{collections::{BTreeMap, HashSet}, path::Path};
+use candle_core::{Device, Tensor};
+use candle_nn::{Linear, Module, VarBuilder};
+use candle_transformers::models::bert::DTYPE;
+use serde::{Deserialize, Serialize};
+use crate::error::Result;
+
+///Лексические веса токенов BGE-M3, для точного поиска по терминам и номерам статей
+/// `indices` - id токенов по возрастанию, `values` - их веса
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct SparseEmbedding
+{
+    pub indices: Vec<u32>,
+    pub values: Vec<f32>
+}
+impl SparseEmbedding
+{
+    ///Лексическое совпадение - сумма произведений весов общих токенов
+    pub fn score(&self, other: &SparseEmbedding) -> f32
+    {
+        let mut score = 0.0;
+        let (mut i, mut j) = (0, 0);
+        while i < self.indices.len() && j < other.indices.len()
+        {
+            match self.indices[i].cmp(&other.indices[j])
+            {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal =>
+                {
+                    score += self.values[i] * other.values[j];
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        score
+    }
+}
+
+///Плотный вектор и лексические веса одного текста
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HybridEmbedding
+{
+    pub dense: Vec<f32>,
+    pub sparse: SparseEmbedding
+}
+
+///`sparse_linear.pt` из репозитория BGE-M3: `Linear(hidden, 1)` над векторами токенов
+pub struct SparseHead
+{
+    linear: Linear,
+    ///служебные токены (`<s>`, `</s>`, `<pad>`, `<unk>`) в лексические веса не попадают
+    special_ids: HashSet<u32>
+}
+impl SparseHead
+{
+    pub fn load(file: &Path, hidden_size: usize, special_ids: HashSet<u32>, device: &Device) -> Result<Self>
+    {
+        let vb = VarBuilder::from_pth(file, DTYPE, device)?;
+        Ok(Self { linear: candle_nn::linear(hidden_size, 1, vb)?, special_ids })
+    }
+    ///`hidden` - `[batch, tokens, hidden]` последнего слоя модели, веса `relu(linear(hidden))`, по каждому id токена берется максимальный
+    pub fn forward(&self, hidden: &Tensor, token_ids: &Tensor, attention_mask: &Tensor) -> Result<Vec<SparseEmbedding>>
+    {
+        let weights: Vec<Vec<f32>> = self.linear.forward(hidden)?.relu()?.squeeze(2)?.to_dtype(candle_core::DType::F32)?.to_vec2()?;
+        let token_ids: Vec<Vec<u32>> = token_ids.to_vec2()?;
+        let attention_mask: Vec<Vec<u32>> = attention_mask.to_vec2()?;
+        Ok(weights.iter().zip(&token_ids).zip(&attention_mask)
+            .map(|((weights, ids), mask)| sparse_weights(ids, weights, mask, &self.special_ids))
+            .collect())
+    }
+}
+
+fn sparse_weights(token_ids: &[u32], weights: &[f32], attention_mask: &[u32], special_ids: &HashSet<u32>) -> SparseEmbedding
+{
+    let mut max: BTreeMap<u32, f32> = BTreeMap::new();
+    for ((id, weight), mask) in token_ids.iter().zip(weights).zip(attention_mask)
+    {
+        if *mask == 0 || *weight <= 0.0 || special_ids.contains(id)
+        {
+            continue;
+        }
+        let current = max.entry(*id).or_insert(0.0);
+        *current = current.max(*weight);
+    }
+    SparseEmbedding
+    {
+        indices: max.keys().copied().collect(),
+        values: max.values().copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::collections::HashSet;
+    use super::SparseEmbedding;
+
+    #[test]
+    fn test_sparse_weights()
+    {
+        let special: HashSet<u32> = [0, 1, 2].into();
+        //<s> статья 5 статья </s> <pad>
+        let sparse = super::sparse_weights(&[0, 40, 7, 40, 2, 1], &[0.9, 0.2, 0.5, 0.3, 0.8, 0.7], &[1, 1, 1, 1, 1, 0], &special);
+        assert_eq!(sparse, SparseEmbedding { indices: vec![7, 40], values: vec![0.5, 0.3] });
+        let other = SparseEmbedding { indices: vec![3, 40], values: vec![1.0, 2.0] };
+        assert!((sparse.score(&other) - 0.6).abs() < 1e-6);
+    }
+}
@@ -6,7 +6,7 @@ use systema_client::Converter;
 use tracing::info;
 use utilites::Date;
 use embedding::{Chunk, ChunkMeta, ChunkedText, Embeddings, Chunker};
-use html_converter::HtmlConverter;
+pub use html_converter::HtmlConverter;
 
 
 //TODO перенести всю логику запроса и его обработки сюда в том числе вычисление эмбеддингов 
@@ -16,8 +16,9 @@ mod tests
     use systema_client::{DocumentNode, DocumentNodes};
     use tracing::{debug, info};
     use utilites::Date;
-    use crate::{Chunk, HtmlConverter, logger};
-    use super::{ChunkMeta, Chunker};
+    use embedding::ChunkOptions;
+    use crate::{HtmlConverter, logger};
+    use super::Chunker;
 
     #[tokio::test]
     async fn test_converter()
@@ -25,41 +26,14 @@ mod tests
         logger::init();
         let converter = HtmlConverter{};
         let result = 
-            systema_client::SystemaClient::get_document(
+            systema_client::SystemaClient::default().get_document(
                 Date::new_date(31, 07, 2025),
                 "287-ФЗ", converter).await.unwrap();  
 
-        let mut chunks = Vec::with_capacity(result.node_count());
         info!("Ноды документы были успешно получены: {} шт.", result.node_count());
         let chunker = Chunker::new().await.unwrap();
-        for node in &result
-        {
-            //бьем текст на куски тут и для каждого создаем чанку
-            let splitted = chunker.split_text(node.converted_content()).await.unwrap();
-            for text in splitted
-            {
-                let chunk = Chunk
-                {
-                    publication_url: result.publication_url().to_owned(),
-                    document_url: format!("http://actual.pravo.gov.ru/list.html#hash={}", result.hash()),
-                    title: result.title().to_owned(),
-                    number: result.number().to_owned(),
-                    sign_date: result.sign_date().to_owned(),
-                    hash: result.hash().to_owned(),
-                    path: result.find_all_parents_as_str(&node),
-                    liks_hashes: node.links_hashes().cloned(),
-                    content: text.content,
-                    embeddings: None,
-                    meta: Some(ChunkMeta
-                    {
-                        chunk_index: text.chunk_index,
-                        token_count: text.token_count
-                    })
-                };
-                chunks.push(chunk);
-            }
-        }
-       
+        let chunks = chunker.chunk_document(&result, &ChunkOptions::default()).await.unwrap();
+        info!("утративших силу узлов: {}", result.repealed_nodes().count());
         for chunk in &chunks
         {
            
@@ -0,0 +1,127 @@
+mod logger;
+use std::path::{Path, PathBuf};
+use pipeline::HtmlConverter;
+use systema_client::{ActualRedactionsClient, ClientConfig, DocumentKind, RedlineMode, SystemaClient};
+use tracing::{error, info};
+use utilites::Date;
+
+const USAGE: &str = "использование:
+    pipeline batch <дата с dd.mm.yyyy> <дата по dd.mm.yyyy> <каталог> [параллельность]
+    pipeline history <дата подписания dd.mm.yyyy> <номер> <адрес, например \"статья 5 часть 2\">
+    pipeline report <дата подписания dd.mm.yyyy> <номер> <новые редакции с dd.mm.yyyy> <каталог>
+    pipeline redline <дата подписания dd.mm.yyyy> <номер> <id предыдущей редакции> <id новой редакции> <файл.html> [inline|side]
+адреса api и прокси берутся из переменных SYSTEMA_EBPI_URL, SYSTEMA_IPS_URL, SYSTEMA_PROXY";
+
+#[tokio::main]
+async fn main()
+{
+    logger::init();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(|a| a.as_str())
+    {
+        Some("batch") => batch(&args[1..]).await,
+        Some("history") => history(&args[1..]).await,
+        Some("report") => report(&args[1..]).await,
+        Some("redline") => redline(&args[1..]).await,
+        _ => Err(USAGE.to_owned())
+    };
+    if let Err(e) = result
+    {
+        error!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn parse_date(arg: Option<&String>) -> Result<Date, String>
+{
+    arg.and_then(|a| Date::parse(a)).ok_or(USAGE.to_owned())
+}
+
+///Загрузка всех ФЗ и ФКЗ подписанных за период, каждый документ сохраняется в `<каталог>/<hash>.json`
+/// итоговый отчет с ошибками в `<каталог>/report.json`
+async fn batch(args: &[String]) -> Result<(), String>
+{
+    let date_from = parse_date(args.get(0))?;
+    let date_to = parse_date(args.get(1))?;
+    let out_dir = PathBuf::from(args.get(2).ok_or(USAGE.to_owned())?);
+    let concurrency = args.get(3).and_then(|c| c.parse().ok()).unwrap_or(4);
+    std::fs::create_dir_all(&out_dir).map_err(|e| e.to_string())?;
+    let report = SystemaClient::new(ClientConfig::from_env()).process_period(date_from, date_to, &[DocumentKind::Fz, DocumentKind::Fkz], concurrency, &HtmlConverter, |doc|
+    {
+        let path = out_dir.join([doc.hash(), ".json"].concat());
+        std::fs::write(&path, serde_json::to_string(&doc)?)?;
+        Ok(())
+    }).await.map_err(|e| e.to_string())?;
+    info!("обработано {} из {} документов, ошибок: {}", report.processed.len(), report.total, report.failures.len());
+    write_json(&out_dir.join("report.json"), &report)
+}
+
+///История изменений положения документа, в stdout выводится json со списком изменений
+async fn history(args: &[String]) -> Result<(), String>
+{
+    let sign_date = parse_date(args.get(0))?;
+    let number = args.get(1).ok_or(USAGE.to_owned())?;
+    let address = args[2.min(args.len())..].join(" ");
+    if address.is_empty()
+    {
+        return Err(USAGE.to_owned());
+    }
+    let config = ClientConfig::from_env();
+    let card = ActualRedactionsClient::new(config.clone()).search_default(sign_date, number).await.map_err(|e| e.to_string())?;
+    let events = SystemaClient::new(config).provision_lookup(HtmlConverter).amendment_history(&card, &address).await.map_err(|e| e.to_string())?;
+    for e in &events
+    {
+        info!("{:?} {} от {} вступает в силу {}", e.kind, e.act.number.as_deref().unwrap_or("?"), e.act.date.as_ref().map(|d| d.to_string()).unwrap_or_default(), e.effective_from.as_ref().map(|d| d.to_string()).unwrap_or("дата неизвестна".to_owned()));
+    }
+    println!("{}", serde_json::to_string_pretty(&events).map_err(|e| e.to_string())?);
+    Ok(())
+}
+
+///Сводки изменений по новым редакциям документа, каждая сохраняется в `<каталог>/<номер>_<id редакции>.md` и `.json`
+async fn report(args: &[String]) -> Result<(), String>
+{
+    let sign_date = parse_date(args.get(0))?;
+    let number = args.get(1).ok_or(USAGE.to_owned())?;
+    let since = parse_date(args.get(2))?;
+    let out_dir = PathBuf::from(args.get(3).ok_or(USAGE.to_owned())?);
+    std::fs::create_dir_all(&out_dir).map_err(|e| e.to_string())?;
+    let config = ClientConfig::from_env();
+    let card = ActualRedactionsClient::new(config.clone()).search_default(sign_date, number).await.map_err(|e| e.to_string())?;
+    let reports = SystemaClient::new(config).provision_lookup(HtmlConverter).redaction_reports(&card, &since).await.map_err(|e| e.to_string())?;
+    for report in &reports
+    {
+        let name = [&number.replace('/', "_"), "_", &report.redaction.id.to_string()].concat();
+        std::fs::write(out_dir.join([&name, ".md"].concat()), report.to_markdown()).map_err(|e| e.to_string())?;
+        write_json(&out_dir.join([&name, ".json"].concat()), report)?;
+        info!("редакция {}: изменений {}", report.redaction.id, report.changes.len());
+    }
+    Ok(())
+}
+
+///Html сравнение двух редакций документа, по умолчанию изменения выделяются в тексте новой редакции
+async fn redline(args: &[String]) -> Result<(), String>
+{
+    let sign_date = parse_date(args.get(0))?;
+    let number = args.get(1).ok_or(USAGE.to_owned())?;
+    let previous_id: u32 = args.get(2).and_then(|id| id.parse().ok()).ok_or(USAGE.to_owned())?;
+    let current_id: u32 = args.get(3).and_then(|id| id.parse().ok()).ok_or(USAGE.to_owned())?;
+    let out = PathBuf::from(args.get(4).ok_or(USAGE.to_owned())?);
+    let mode = match args.get(5).map(|m| m.as_str())
+    {
+        Some("side") => RedlineMode::SideBySide,
+        None | Some("inline") => RedlineMode::Inline,
+        _ => return Err(USAGE.to_owned())
+    };
+    let config = ClientConfig::from_env();
+    let card = ActualRedactionsClient::new(config.clone()).search_default(sign_date, number).await.map_err(|e| e.to_string())?;
+    let html = SystemaClient::new(config).provision_lookup(HtmlConverter).redline(&card, previous_id, current_id, mode).await.map_err(|e| e.to_string())?;
+    std::fs::write(&out, html).map_err(|e| e.to_string())?;
+    info!("сравнение редакций {} и {} сохранено в {}", previous_id, current_id, out.display());
+    Ok(())
+}
+
+fn write_json<T: serde::Serialize>(path: &Path, value: &T) -> Result<(), String>
+{
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| e.to_string())
+}
@@ -4,7 +4,6 @@ use tracing::info;
 use crate::error::{Error, Result};
 use crate::model::LongContextModel;
 use scraper::{ElementRef, Html, Node, Selector};
-use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DocumentChunk {
@@ -553,46 +552,8 @@ impl LegalDocumentChunker {
     Ok(())
 }
     
-    // Дополнительный метод для извлечения структурированной информации
-    pub async fn extract_legal_metadata(&self, html: &str) -> Result<HashMap<String, String>> {
-        let document = Html::parse_document(html);
-        let mut metadata = HashMap::new();
-        
-        // Извлекаем номер закона
-        let number_selector = Selector::parse("p.I").unwrap();
-        for element in document.select(&number_selector) {
-            let text = element.text().collect::<String>();
-            if text.contains("№") && text.contains("ФЗ") {
-                metadata.insert("law_number".to_string(), text);
-                break;
-            }
-        }
-        
-        // Извлекаем дату принятия
-        let date_selectors = [
-            Selector::parse("p.I").unwrap(),
-            Selector::parse("p.T").unwrap(),
-        ];
-        
-        for selector in date_selectors.iter() {
-            for element in document.select(selector) {
-                let text = element.text().collect::<String>();
-                if text.contains("декабря") || text.contains("года") {
-                    metadata.insert("date".to_string(), text);
-                    break;
-                }
-            }
-            if metadata.contains_key("date") {
-                break;
-            }
-        }
-        
-        // Извлекаем название
-        let title_selector = Selector::parse("p.T").unwrap();
-        if let Some(element) = document.select(&title_selector).nth(1) {
-            metadata.insert("title".to_string(), element.text().collect());
-        }
-        
-        Ok(metadata)
+    // Реквизиты документа (вид, даты принятия и одобрения, подписант, номер)
+    pub async fn extract_legal_metadata(&self, html: &str) -> Result<systema_client::DocumentMetadata> {
+        Ok(systema_client::DocumentMetadata::from_html(html))
     }
 }
\ No newline at end of file
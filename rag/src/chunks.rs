@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use systema_client::DocumentState;
 use crate::document::{Document, Section};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -8,7 +9,11 @@ pub struct Chunk
     pub document_title: Vec<String>,
     pub document_number: String,
     pub document_sign_date: String,
+    pub document_state: DocumentState,
     pub section_article: Option<String>,
+    /// Номер приложения к документу, если чанк из приложения
+    #[serde(default)]
+    pub appendix: Option<String>,
     pub content: String,
     pub metadata: ChunkMetadata,
 }
@@ -424,8 +429,10 @@ impl DocumentChunker {
             document_uri: document.uri().to_string(),
             document_title: document.title().clone(),
             section_article: section.article.clone(),
+            appendix: section.appendix.clone(),
             document_number: document.number().to_owned(),
             document_sign_date: document.date().to_owned(),
+            document_state: document.state(),
             content: content.to_string(),
             metadata: ChunkMetadata {
                 chunk_index,
@@ -514,11 +521,13 @@ mod tests
         
         // Добавляем секции как в вашем примере
         doc.add_section(Section {
+            appendix: None,
             article: Some("Статья 36. Стипендии и другие денежные выплаты".to_string()),
             content: "5. Государственная социальная стипендия назначается студентам, являющимся детьми-сиротами и детьми, оставшимися без попечения родителей, лицами из числа детей-сирот и детей, оставшихся без попечения родителей, лицами, потерявшими в период обучения обоих родителей или единственного родителя, детьми-инвалидами, инвалидами I и II групп, инвалидами с детства, студентами, подвергшимися воздействию радиации вследствие катастрофы на Чернобыльской АЭС и иных радиационных катастроф, вследствие ядерных испытаний на Семипалатинском полигоне, студентами, являющимися инвалидами вследствие военной травмы или заболевания, полученных в период прохождения военной службы, и ветеранами боевых действий, а также студентами из числа граждан, проходивших в течение не менее трех лет военную службу по контракту на воинских должностях, подлежащих замещению солдатами, матросами, сержантами, старшинами, и уволенных с военной службы по основаниям, предусмотренным подпунктами \"б\" - \"г\" пункта 1, подпунктом \"а\" пункта 2 и подпунктами \"а\" - \"в\" пункта 3 статьи 51 Федерального закона от 28 марта 1998 года № 53-ФЗ \"О воинской обязанности и военной службе\". Государственная социальная стипендия назначается также студентам, получившим государственную социальную помощь. Государственная социальная стипендия назначается указанной категории студентов со дня представления в организацию, осуществляющую образовательную деятельность, документа, подтверждающего назначение государственной социальной помощи, на один год со дня назначения указанной государственной социальной помощи. (В редакции Федерального закона от 29.12.2017 № 473-ФЗ)".to_string(),
         });
         
         doc.add_section(Section {
+            appendix: None,
             article: Some("Статья 36. Стипендии и другие денежные выплаты".to_string()),
             content: "6. Аспирантам, ординаторам, ассистентам-стажерам, обучающимся по очной форме обучения за счет бюджетных ассигнований федерального бюджета, в порядке, установленном федеральным органом исполнительной власти, осуществляющим функции по выработке и реализации государственной политики и нормативно-правовому регулированию в сфере высшего образования, назначаются государственные стипендии. (В редакции Федерального закона от 26.07.2019 № 232-ФЗ)".to_string(),
         });
@@ -574,6 +583,7 @@ mod tests
         }
         
         doc.add_section(Section {
+            appendix: None,
             article: Some("Большая секция".to_string()),
             content: large_content,
         });
@@ -0,0 +1,87 @@
+use std::path::Path;
+use candle_core::{Device, Tensor};
+use candle_nn::{Linear, Module, VarBuilder};
+use candle_transformers::models::bert::DTYPE;
+use crate::error::Result;
+
+/// Голова ColBERT из BGE-M3 (`colbert_linear.pt`, `Linear(hidden, hidden)`),
+/// каждый токен текста получает свой нормализованный вектор
+pub struct ColbertHead
+{
+    linear: Linear,
+}
+
+impl ColbertHead
+{
+    pub fn load(file: &Path, hidden_size: usize, device: &Device) -> Result<Self>
+    {
+        let vb = VarBuilder::from_pth(file, DTYPE, device)?;
+        Ok(Self { linear: candle_nn::linear(hidden_size, hidden_size, vb)? })
+    }
+
+    /// `hidden` - `[n, tokens, hidden]` последнего слоя, `attention_mask` - `[n, tokens]`, векторы токенов каждого из `n` текстов
+    /// токен `[CLS]` (первый) и паддинг отбрасываются, как в BGE-M3
+    pub fn forward(&self, hidden: &Tensor, attention_mask: &Tensor) -> Result<Vec<Vec<Vec<f32>>>>
+    {
+        let (batch, tokens) = (hidden.dim(0)?, hidden.dim(1)?);
+        if tokens < 2
+        {
+            return Ok(vec![Vec::new(); batch]);
+        }
+        let vectors = self.linear.forward(&hidden.narrow(1, 1, tokens - 1)?)?;
+        let norm = vectors.sqr()?.sum_keepdim(2)?.sqrt()?;
+        let vectors: Vec<Vec<Vec<f32>>> = vectors.broadcast_div(&norm)?.to_dtype(candle_core::DType::F32)?.to_vec3()?;
+        let mask: Vec<Vec<u32>> = attention_mask.narrow(1, 1, tokens - 1)?.to_vec2()?;
+        Ok(vectors.into_iter()
+            .zip(mask)
+            .map(|(vectors, mask)| vectors.into_iter().zip(mask).filter(|(_, m)| *m != 0).map(|(v, _)| v).collect())
+            .collect())
+    }
+}
+
+/// Позднее взаимодействие (MaxSim): для каждого токена запроса лучшее совпадение среди токенов документа,
+/// среднее по токенам запроса, векторы должны быть нормализованы
+pub fn max_sim(query: &[Vec<f32>], document: &[Vec<f32>]) -> f32
+{
+    if query.is_empty() || document.is_empty()
+    {
+        return 0.0;
+    }
+    let dot = |a: &[f32], b: &[f32]| a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+    let sum: f32 = query.iter()
+        .map(|q| document.iter().map(|d| dot(q, d)).fold(f32::MIN, f32::max))
+        .sum();
+    sum / query.len() as f32
+}
+
+#[cfg(test)]
+mod tests
+{
+    use candle_core::{Device, Tensor};
+    use candle_nn::Linear;
+    use super::{ColbertHead, max_sim};
+
+    #[test]
+    fn test_max_sim()
+    {
+        let query = vec![vec![1.0, 0.0], vec![0.0, 1.0]];
+        let close = vec![vec![0.0, 1.0], vec![1.0, 0.0], vec![0.6, 0.8]];
+        let far = vec![vec![0.6, 0.8]];
+        assert!((max_sim(&query, &close) - 1.0).abs() < 1e-6);
+        assert!((max_sim(&query, &far) - 0.7).abs() < 1e-6);
+        assert_eq!(max_sim(&query, &[]), 0.0);
+    }
+
+    #[test]
+    fn test_forward_batch()
+    {
+        let device = Device::Cpu;
+        let head = ColbertHead { linear: Linear::new(Tensor::eye(2, candle_core::DType::F32, &device).unwrap(), None) };
+        let hidden = Tensor::new(&[[[9.0f32, 9.0], [3.0, 4.0], [0.0, 2.0]], [[9.0, 9.0], [2.0, 0.0], [1.0, 1.0]]], &device).unwrap();
+        let mask = Tensor::new(&[[1u32, 1, 1], [1, 1, 0]], &device).unwrap();
+        let vectors = head.forward(&hidden, &mask).unwrap();
+        assert_eq!(vectors.len(), 2);
+        assert_eq!(vectors[0], vec![vec![0.6, 0.8], vec![0.0, 1.0]]);
+        assert_eq!(vectors[1], vec![vec![1.0, 0.0]]);
+    }
+}
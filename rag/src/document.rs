@@ -1,4 +1,6 @@
+use std::fmt::Debug;
 use serde::{Deserialize, Serialize};
+use systema_client::{DocumentNodes, DocumentState};
 use utilites::{Date, http::Uri};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -8,6 +10,8 @@ pub struct Document
     uri: String,
     sign_date: String,
     number: String,
+    #[serde(default)]
+    state: DocumentState,
     sections: Vec<Section>
 }
 impl Document
@@ -20,9 +24,39 @@ impl Document
             uri: uri.to_owned(),
             sections: Vec::new(),
             sign_date,
-            number: number.to_owned() 
+            number: number.to_owned(),
+            state: DocumentState::default()
         }
     }
+    /// Документ из дерева узлов, секции - узлы без дочерних узлов, у узлов приложений номер приложения
+    pub fn from_nodes<C: ToString + Debug>(nodes: &DocumentNodes<C>) -> Self
+    {
+        let mut document = Self::new(&nodes.document_url(), nodes.sign_date().format(utilites::DateFormat::SerializeDate), nodes.number());
+        document.add_title(nodes.title().to_owned());
+        document.set_state(nodes.state());
+        for idx in 0..nodes.node_count()
+        {
+            let Some(node) = nodes.get_node(idx) else { continue };
+            if !nodes.get_children(idx).is_empty() || nodes.is_node_repealed(node) || node.original_content().trim().is_empty()
+            {
+                continue;
+            }
+            let article = nodes.find_all_parents_by_node(node).into_iter()
+                .find(|p| p.content_type().to_lowercase().starts_with("стать"))
+                .map(|p| p.caption().trim_start_matches('$').trim().to_owned());
+            document.add_section(Section
+            {
+                article,
+                appendix: nodes.appendix_of(node).map(|a| a.number.clone()),
+                content: node.text_with_notes()
+            });
+        }
+        document
+    }
+    pub fn set_state(&mut self, state: DocumentState)
+    {
+        self.state = state;
+    }
     pub fn add_title(&mut self, title: String)
     {
         self.title.push(title);
@@ -48,11 +82,17 @@ impl Document
     pub fn date(&self) -> &str {
         &self.sign_date
     }
+    pub fn state(&self) -> DocumentState {
+        self.state
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Section
 {
     pub article: Option<String>,
+    /// Номер приложения к документу
+    #[serde(default)]
+    pub appendix: Option<String>,
     pub content: String
 }
@@ -1,6 +1,6 @@
 use scraper::{ElementRef, Selector, element_ref::Text};
-use systema_client::{DocumentKindSearchParams, SystemaIpsApi};
-use tracing::info;
+use systema_client::{ActualRedactionsClient, Appendix, ClientConfig, DocumentKindSearchParams, DocumentState, SystemaIpsApi};
+use tracing::{info, warn};
 use utilites::Date;
 use crate::{document::{Document, Section}};
 use crate::error::{Error, Result};
@@ -16,7 +16,19 @@ impl DocumentHandler
     pub async fn parse_document(number: &str, sign_date: Date) -> Result<Document>
     {
         let sign_date_str = sign_date.format(utilites::DateFormat::SerializeDate);
+        let config = ClientConfig::from_env();
+        // в ИПС состояния документа нет, берем его из карточки api актуальных редакций
+        let state = match ActualRedactionsClient::new(config.clone()).search_default(sign_date.clone(), number).await
+        {
+            Ok(card) => DocumentState::from(card.doc_state.as_str()),
+            Err(e) =>
+            {
+                warn!("Не удалось получить состояние документа {} от {}: {}", number, sign_date_str, e);
+                DocumentState::Unknown
+            }
+        };
         let document = SystemaIpsApi::search(
+            &config,
             &[DocumentKindSearchParams::Fz, DocumentKindSearchParams::Fkz],
             number,
             sign_date).await?;
@@ -24,9 +36,11 @@ impl DocumentHandler
         let current_uri = document.current_uri();
         let body_selector = Selector::parse("body").unwrap();
         let mut current_article: Option<String> = None;
+        let mut current_appendix: Option<String> = None;
         if let Some(body) = html.select(&body_selector).next()
         {
             let mut document = Document::new(document.current_uri(), sign_date_str, number);
+            document.set_state(state);
             for node in body.children() 
             {
                 let mut current_section: Option<Section> = None;
@@ -41,6 +55,12 @@ impl DocumentHandler
                         {
                             continue;
                         }
+                        else if let Some(number) = Appendix::number_from_heading(&text)
+                        {
+                            // все что после заголовка приложения относится к приложению, статей там нет
+                            current_appendix = Some(number);
+                            current_article = None;
+                        }
                         else if element.classes().any(|a| a == "T")
                         {
                             document.add_title(element_ref.text().collect());
@@ -52,7 +72,7 @@ impl DocumentHandler
                         } 
                         else if element.classes().count() == 0 && tag_name == "p"
                         {
-                            current_section = Some( Section { article: current_article.clone(), content: Self::content_handler(element_ref.text())});
+                            current_section = Some( Section { article: current_article.clone(), appendix: current_appendix.clone(), content: Self::content_handler(element_ref.text())});
                         }
                         if let Some(cs) = current_section
                         {
@@ -1,21 +1,26 @@
 use candle_core::{DType, Device, IndexOp, Tensor};
-use candle_nn::VarBuilder;
-use candle_transformers::models::bert::{BertModel, Config, DTYPE};
-use tokenizers::{PaddingParams, Tokenizer};
+use candle_transformers::models::{bert::DTYPE, xlm_roberta::{Config, XLMRobertaModel}};
+use tokenizers::{Encoding, PaddingParams, Tokenizer};
 use tracing::{info, warn};
 use std::path::Path;
+use embedding::{ModelFiles, ModelSource};
 use std::sync::Arc;
 
-use crate::{error::{Error, Result}, model::LongContextModel};
+use crate::{colbert::{self, ColbertHead}, error::{Error, Result}, model::LongContextModel};
+
+/// Сколько текстов за раз прогоняется через модель для векторов ColBERT, у BGE-M3 контекст до 8192 токенов
+const COLBERT_BATCH: usize = 8;
 
 pub struct LongContextEmbedder 
 {
     model_type: LongContextModel,
+    files: ModelFiles,
     tokenizer: Tokenizer,
     device: Device,
-    model: Option<BertModel>,
+    model: Option<XLMRobertaModel>,
     // Для BGE-M3 можно использовать ColBERT-like подход
     use_colbert: bool,
+    colbert: Option<ColbertHead>,
     max_length: usize,
 }
 
@@ -26,8 +31,9 @@ impl LongContextEmbedder
        
         let device = Device::cuda_if_available(0).unwrap_or(Device::Cpu);
         info!("Using device: {:?}", device);
-        // Загружаем токенизатор
-        let mut tokenizer = Self::load_tokenizer(model_type)?;
+        // Файлы модели ищутся один раз, токенизатор берется из них же, без обращения к сети
+        let files = Self::model_files(model_type)?;
+        let mut tokenizer = Self::load_tokenizer(model_type, &files)?;
         
         // Настраиваем padding для длинного контекста
         let max_length = model_type.max_tokens();
@@ -36,9 +42,10 @@ impl LongContextEmbedder
             strategy: tokenizers::PaddingStrategy::Fixed(max_length),
             direction: tokenizers::PaddingDirection::Right,
             pad_to_multiple_of: None,
-            pad_id: 0,
+            // у XLM-R паддинг `<pad>` с id 1, по нему модель отсчитывает позиции токенов
+            pad_id: 1,
             pad_type_id: 0,
-            pad_token: "[PAD]".to_string(),
+            pad_token: "<pad>".to_string(),
         };
         
         tokenizer.with_padding(Some(padding));
@@ -46,50 +53,34 @@ impl LongContextEmbedder
         Ok(Self 
         {
             model_type,
+            files,
             tokenizer,
             device,
             model: None,
             use_colbert: true,
+            colbert: None,
             max_length,
         })
     }
 
     pub fn with_model(mut self) -> Result<Self>
     {
-        let model = Self::load_model(self.model_type, &self.device)?;
-        self.model = model;
+        self.model = Self::load_model(self.model_type, &self.files, &self.device)?;
+        if self.use_colbert
+        {
+            self.colbert = Self::load_colbert(self.model_type, &self.files, &self.device)?;
+        }
         Ok(self)
     }
     
-    fn load_tokenizer(model_type: LongContextModel) -> Result<Tokenizer> 
+    fn load_tokenizer(model_type: LongContextModel, files: &ModelFiles) -> Result<Tokenizer> 
     {
-        let model_name = model_type.model_name();
-        
-        match model_type 
-        {
-            LongContextModel::Bge => 
+        Tokenizer::from_file(&files.tokenizer)
+            .map_err(|e| Error::ModelLoadError
             {
-                // BGE-M3 использует специальный токенизатор
-                let tokenizer = Tokenizer::from_pretrained(model_name, None)?;
-                
-                // Для BGE-M3 можем использовать предобученный токенизатор
-                Ok(tokenizer)
-            },
-            LongContextModel::BgeReranker => 
-            {
-                // BGE-Reranker использует инструкции для запросов и документов
-                let mut tokenizer = Tokenizer::from_pretrained(model_name, None)?;
-                
-                // Добавляем специальные токены для инструкций
-                tokenizer.add_special_tokens(&[
-                    tokenizers::AddedToken::from("[CLS]", true),
-                    tokenizers::AddedToken::from("[SEP]", true),
-                ]);
-                
-                Ok(tokenizer)
-            },
-            _ => Ok(Tokenizer::from_pretrained(model_name, None)?)
-        }
+                model: model_type.model_name().to_string(),
+                source: e,
+            })
     }
 
     pub fn get_tokenizer(&self) -> &Tokenizer
@@ -97,49 +88,56 @@ impl LongContextEmbedder
         &self.tokenizer
     }
     
-    fn load_model(model_type: LongContextModel, device: &Device) -> Result<Option<BertModel>> 
+    // Файлы модели из кеша hf-hub, с проверкой контрольных сумм
+    fn model_files(model_type: LongContextModel) -> Result<ModelFiles>
     {
         let model_name = model_type.model_name();
-        info!("Loading model: {}", model_name);
-        
-        // Создаем конфигурацию модели
-        let config = Config
-        { 
-            vocab_size: 250002,
-            hidden_size: 1024,
-            num_hidden_layers: 24,
-            num_attention_heads: 16,
-            intermediate_size: 4096,
-            hidden_act: candle_transformers::models::bert::HiddenAct::Gelu,
-            hidden_dropout_prob: 0.1f64,
-            max_position_embeddings: 8194,
-            type_vocab_size: 1,
-            initializer_range: 0.02f64,
-            layer_norm_eps: 0.00001f64,
-            pad_token_id: 1,
-            position_embedding_type: candle_transformers::models::bert::PositionEmbeddingType::Absolute,
-            use_cache: true,
-            classifier_dropout: None,
-            model_type: Some("xlm-roberta".to_owned())
+        let load_error = |e: Box<dyn std::error::Error + Send + Sync>| Error::ModelLoadError
+        {
+            model: model_name.to_string(),
+            source: e,
         };
-        
-        // Загружаем веса модели
-        let weights_path = format!("{}/model.safetensors", model_name);
-        let vb = unsafe {
-            VarBuilder::from_mmaped_safetensors(&[weights_path], DTYPE, device)
-                .map_err(|e| Error::ModelLoadError {
-                    model: model_name.to_string(),
-                    source: e.into(),
-                })?
+        ModelFiles::resolve_local(&ModelSource::new(model_name))
+            .map_err(|e| load_error(e.into()))?
+            .ok_or_else(|| load_error("файлы модели не найдены в кеше hf-hub".into()))
+    }
+
+    // Голова ColBERT есть только у BGE-M3
+    fn load_colbert(model_type: LongContextModel, files: &ModelFiles, device: &Device) -> Result<Option<ColbertHead>>
+    {
+        let Some(file) = &files.colbert_linear else
+        {
+            warn!("У модели {} нет colbert_linear.pt, векторы токенов недоступны", model_type.model_name());
+            return Ok(None);
         };
-        
-        // Создаем модель BERT
-        let model = BertModel::load(vb, &config)
-            .map_err(|e| Error::ModelLoadError 
+        let head = ColbertHead::load(file, model_type.dimension(), device)
+            .map_err(|e| Error::ModelLoadError
             {
-                model: model_name.to_string(),
+                model: model_type.model_name().to_string(),
                 source: e.into(),
             })?;
+        Ok(Some(head))
+    }
+
+    fn load_model(model_type: LongContextModel, files: &ModelFiles, device: &Device) -> Result<Option<XLMRobertaModel>> 
+    {
+        let model_name = model_type.model_name();
+        info!("Loading model: {}", model_name);
+        
+        let load_error = |e: Box<dyn std::error::Error + Send + Sync>| Error::ModelLoadError
+        {
+            model: model_name.to_string(),
+            source: e,
+        };
+        // Конфигурация из config.json модели
+        let config = std::fs::read_to_string(&files.config).map_err(|e| load_error(e.into()))?;
+        let config: Config = serde_json::from_str(&config).map_err(|e| load_error(e.into()))?;
+        
+        // Загружаем веса модели, safetensors через mmap
+        let vb = files.weights.var_builder(DTYPE, device).map_err(|e| load_error(e.into()))?;
+        
+        // BGE-M3 это XLM-R: позиции токенов начинаются после `pad_token_id`, а не с 0 как у BERT
+        let model = XLMRobertaModel::new(&config, vb).map_err(|e| load_error(e.into()))?;
         
         Ok(Some(model))
     }
@@ -149,11 +147,97 @@ impl LongContextEmbedder
         match self.model_type 
         {
             LongContextModel::Bge => self.embed_bge_m3(text).await,
-            LongContextModel::BgeReranker => self.embed_bge_reranker(text).await,
+            // кросс-энкодер векторов не дает, он оценивает пары (запрос, текст), см. `Reranker`
+            LongContextModel::BgeReranker => Err(Error::NotEmbeddingModel
+            {
+                model_name: self.model_type.model_name().to_string(),
+            }),
             _ => self.embed_general(text).await,
         }
     }
     
+    /// Векторы токенов текста (ColBERT) для позднего взаимодействия, см. `colbert::max_sim`
+    pub async fn embed_colbert(self: &Arc<Self>, text: &str) -> Result<Vec<Vec<f32>>>
+    {
+        let embedder = self.clone();
+        let text = text.to_owned();
+        let mut vectors = tokio::task::spawn_blocking(move || embedder.colbert_vectors(&[&text])).await??;
+        Ok(vectors.pop().unwrap_or_default())
+    }
+
+    /// Второй этап поиска: кандидаты, найденные по плотному вектору, пересортировываются по MaxSim
+    /// `texts` - тексты кандидатов, возвращаются индексы кандидатов и их оценки по убыванию
+    pub async fn rerank_colbert(self: &Arc<Self>, query: &str, texts: &[&str]) -> Result<Vec<(usize, f32)>>
+    {
+        let embedder = self.clone();
+        let query = query.to_owned();
+        let texts: Vec<String> = texts.iter().map(|t| t.to_string()).collect();
+        tokio::task::spawn_blocking(move ||
+        {
+            let query = embedder.colbert_vectors(&[&query])?.pop().unwrap_or_default();
+            let texts: Vec<&str> = texts.iter().map(|t| t.as_str()).collect();
+            let mut scores: Vec<(usize, f32)> = embedder.colbert_vectors(&texts)?
+                .iter()
+                .enumerate()
+                .map(|(i, document)| (i, colbert::max_sim(&query, document)))
+                .collect();
+            scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+            Ok(scores)
+        }).await?
+    }
+
+    /// Векторы токенов текстов, через модель прогоняется по `COLBERT_BATCH` текстов за раз
+    fn colbert_vectors(&self, texts: &[&str]) -> Result<Vec<Vec<Vec<f32>>>>
+    {
+        let head = self.colbert.as_ref()
+            .ok_or_else(|| Error::ModelNotLoaded
+            {
+                model_name: [self.model_type.model_name(), " (colbert)"].concat(),
+            })?;
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(COLBERT_BATCH)
+        {
+            let (hidden_states, attention_mask) = self.last_hidden_states(batch)?;
+            vectors.extend(head.forward(&hidden_states, &attention_mask)?);
+        }
+        Ok(vectors)
+    }
+
+    /// Последний слой модели `[n, tokens, hidden]` и маска `[n, tokens]` для пачки текстов,
+    /// паддинг обрезается по самому длинному тексту пачки
+    fn last_hidden_states(&self, texts: &[&str]) -> Result<(Tensor, Tensor)>
+    {
+        let model = self.model.as_ref()
+            .ok_or_else(|| Error::ModelNotLoaded {
+                model_name: self.model_type.model_name().to_string(),
+            })?;
+        let encodings = self.tokenizer
+            .encode_batch(texts.to_vec(), true)
+            .map_err(|e| Error::TokenizationError {
+                text: texts.join("\n"),
+                source: e.into(),
+            })?;
+        let len = encodings.iter()
+            .map(|e| e.get_attention_mask().iter().filter(|m| **m != 0).count())
+            .max()
+            .unwrap_or(0)
+            .min(self.max_length);
+        let tensor = |values: fn(&Encoding) -> &[u32]| -> Result<Tensor>
+        {
+            let rows = encodings.iter()
+                .map(|e| Tensor::new(&values(e)[..len], &self.device))
+                .collect::<candle_core::Result<Vec<_>>>()?;
+            Ok(Tensor::stack(&rows, 0)?)
+        };
+        let input_ids = tensor(|e| e.get_ids())?;
+        let tt_ids = tensor(|e| e.get_type_ids())?;
+        let attention_mask = tensor(|e| e.get_attention_mask())?;
+        let hidden_states = model
+            .forward(&input_ids, &attention_mask, &tt_ids, None, None, None)
+            .map_err(|e| Error::InferenceError { source: e })?;
+        Ok((hidden_states, attention_mask))
+    }
+    
     async fn embed_bge_m3(&self, text: &str) -> Result<Vec<Vec<f32>>> 
     {
         // BGE-M3 требует инструкций для разных типов текста
@@ -229,40 +313,6 @@ impl LongContextEmbedder
         Ok(vec![normalized_embeddings])
     }
     
-    async fn embed_bge_reranker(&self, text: &str) -> Result<Vec<Vec<f32>>> {
-        // BGE-Reranker может использовать специальные токены
-        let formatted_text = if !text.contains("[CLS]") {
-            format!("[CLS]{}[SEP]", text)
-        } else {
-            text.to_string()
-        };
-        
-        // Токенизация
-        let encoding = self.tokenizer
-            .encode(formatted_text, true)
-            .map_err(|e| Error::TokenizationError {
-                text: text.to_string(),
-                source: e.into(),
-            })?;
-        
-        let input_ids = encoding.get_ids();
-        let tt_ids = encoding.get_type_ids();
-        let attention_mask = encoding.get_attention_mask();
-        
-        // Подготавливаем тензоры
-        let input_ids_tensor = Tensor::new(input_ids, &self.device)
-            .map_err(|e| Error::TensorError { source: e })?;
-         let tt_ids_tensor = Tensor::new(tt_ids, &self.device)
-            .map_err(|e| Error::TensorError { source: e })?;
-        let attention_mask_tensor = Tensor::new(attention_mask, &self.device)
-            .map_err(|e| Error::TensorError { source: e })?;
-        
-        // Получаем эмбеддинги
-        let embeddings = self.get_model_embeddings(input_ids_tensor, tt_ids_tensor, attention_mask_tensor).await?;
-        
-        Ok(vec![embeddings])
-    }
-    
     async fn embed_general(&self, text: &str) -> Result<Vec<Vec<f32>>> {
         // Общая реализация для других моделей
         let encoding = self.tokenizer
@@ -309,10 +359,12 @@ impl LongContextEmbedder
             .map_err(|e| Error::TensorError { source: e })?;
         let attention_mask = attention_mask.unsqueeze(0)
             .map_err(|e| Error::TensorError { source: e })?;
+        let tt_ids = tt_ids.unsqueeze(0)
+            .map_err(|e| Error::TensorError { source: e })?;
         
         // Получаем выход модели
         let hidden_states = model
-            .forward(&input_ids, &tt_ids, Some(&attention_mask))
+            .forward(&input_ids, &attention_mask, &tt_ids, None, None, None)
             .map_err(|e| Error::InferenceError { source: e })?;
         
         // Используем embedding токена [CLS] для получения векторного представления
@@ -436,4 +488,33 @@ impl LongContextEmbedder
         let count = embeddings.len() as f32;
         sum.iter().map(|&x| x / count).collect()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests
+{
+    use std::sync::Arc;
+    use crate::{colbert, model::LongContextModel};
+    use super::LongContextEmbedder;
+
+    /// нужна BAAI/bge-m3 в кеше hf-hub, эталонные оценки ColBERT из примера в карточке модели
+    /// (`BGEM3FlagModel.colbert_score`: 0.7797 и 0.4620)
+    #[tokio::test]
+    #[ignore]
+    async fn test_colbert_reference_scores()
+    {
+        let embedder = Arc::new(LongContextEmbedder::new(LongContextModel::Bge).unwrap().with_model().unwrap());
+        let query = embedder.embed_colbert("What is BGE M3?").await.unwrap();
+        let bge = embedder.embed_colbert("BGE M3 is an embedding model supporting dense retrieval, lexical matching and multi-vector interaction.").await.unwrap();
+        let bm25 = embedder.embed_colbert("BM25 is a bag-of-words retrieval function that ranks a set of documents based on the query terms appearing in each document").await.unwrap();
+        assert!((colbert::max_sim(&query, &bge) - 0.7797).abs() < 1e-3);
+        assert!((colbert::max_sim(&query, &bm25) - 0.4620).abs() < 1e-3);
+        //пачкой те же векторы, паддинг короткого текста на оценки не влияет
+        let ranked = embedder.rerank_colbert("What is BGE M3?", &[
+            "BM25 is a bag-of-words retrieval function that ranks a set of documents based on the query terms appearing in each document",
+            "BGE M3 is an embedding model supporting dense retrieval, lexical matching and multi-vector interaction.",
+        ]).await.unwrap();
+        assert_eq!(ranked[0].0, 1);
+        assert!((ranked[0].1 - 0.7797).abs() < 1e-3);
+        assert!((ranked[1].1 - 0.4620).abs() < 1e-3);
+    }
+}
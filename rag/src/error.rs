@@ -81,4 +81,17 @@ pub enum Error
     {
         model_name: String,
     },
+
+    #[error("Model {} is a cross-encoder and does not produce embeddings, use Reranker", model_name)]
+    NotEmbeddingModel 
+    {
+        model_name: String,
+    },
+
+    #[error("Blocking inference task failed: {}", source)]
+    BlockingTaskError
+    {
+        #[from]
+        source: tokio::task::JoinError,
+    },
 }
\ No newline at end of file
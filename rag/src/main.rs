@@ -1,12 +1,15 @@
-use systema_client::{DocumentKindSearchParams, SystemaIpsApi};
+use std::path::PathBuf;
+use scraper::Html;
+use systema_client::{DocumentResponse, LegalSource, LocalFileSource, LocalMetadata};
 use tracing::info;
-use utilites::Date;
 
 use crate::chunking::DocumentMetadata;
 
 mod logger;
 mod model;
 mod embedding;
+mod colbert;
+mod reranker;
 mod error;
 mod chunking;
 mod structure;
@@ -23,12 +26,14 @@ async fn main()
     logger::init();
     let chunk = chunking::LegalDocumentChunker::new(model::LongContextModel::BgeReranker);
     let tokenizer = embedding::LongContextEmbedder::new(model::LongContextModel::BgeReranker).unwrap();
-    let document = SystemaIpsApi::search(
-            &[DocumentKindSearchParams::Fz, DocumentKindSearchParams::Fkz],
-            "273-фз",
-            Date::new_date(29, 12, 2012)).await.unwrap();
-    let html = document.get_document_html().await.unwrap();
-    let cc = chunk.chunk_document(html, &DocumentMetadata::new("www.w.ru".to_owned(), "TEST".to_owned()), tokenizer.get_tokenizer()).await.unwrap();
+    //сохраненный html можно передать путем (рядом реквизиты `<имя>.json` и содержание `<имя>.contents.json`), без аргументов берется test_doc1.html
+    let document = match std::env::args().nth(1)
+    {
+        Some(path) => LocalFileSource.fetch(&PathBuf::from(path)).await.unwrap(),
+        None => DocumentResponse::from_html("test_doc1", TEST_DOC1, LocalMetadata::default(), None).unwrap()
+    };
+    let html = Html::parse_document(&document.html);
+    let cc = chunk.chunk_document(html, &DocumentMetadata::new(document.publication_url.clone(), document.name.clone()), tokenizer.get_tokenizer()).await.unwrap();
     info!("chunks: {:#?}", cc);
 }
 
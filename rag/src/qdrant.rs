@@ -1,3 +1,4 @@
+use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 use uuid::Uuid;
@@ -5,9 +6,11 @@ use qdrant_client::qdrant::{
     Condition, CreateCollectionBuilder, FieldCondition, Filter, PointStruct, ScoredPoint, SearchPoints, Value, WithPayloadSelector
 };
 use qdrant_client::Qdrant;
+use systema_client::DocumentState;
 use crate::chunks::{Chunk, DocumentChunker};
 use crate::error::{Result, Error};
 use crate::embedding::LongContextEmbedder;
+use crate::reranker::Reranker;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct QdrantPoint {
@@ -16,6 +19,32 @@ pub struct QdrantPoint {
     pub payload: QdrantPayload,
 }
 
+impl QdrantPoint {
+    /// Точка для чанка с новым идентификатором
+    pub fn from_chunk(chunk: &Chunk, vector: Vec<f32>, embedding_text: &str) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            vector,
+            payload: QdrantPayload {
+                text: chunk.content.clone(),
+                embedding_text: embedding_text.to_string(),
+                document_uri: chunk.document_uri.clone(),
+                document_title: chunk.document_title.clone(),
+                document_number: chunk.document_number.clone(),
+                document_sign_date: chunk.document_sign_date.clone(),
+                document_state: chunk.document_state,
+                section_article: chunk.section_article.clone(),
+                appendix: chunk.appendix.clone(),
+                chunk_index: chunk.metadata.chunk_index,
+                total_chunks: chunk.metadata.total_chunks,
+                section_index: chunk.metadata.section_index,
+                char_count: chunk.metadata.char_count,
+                is_overlap: chunk.metadata.is_overlap,
+            },
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct QdrantPayload {
     pub text: String,                // Оригинальный текст чанка
@@ -24,7 +53,11 @@ pub struct QdrantPayload {
     pub document_title: Vec<String>, // Заголовки документа
     pub document_number: String,    // Номер документа
     pub document_sign_date: String, // Дата подписания документа
+    #[serde(default)]
+    pub document_state: DocumentState, // Правовое состояние документа
     pub section_article: Option<String>, // Статья/раздел
+    #[serde(default)]
+    pub appendix: Option<String>,    // Номер приложения к документу
     pub chunk_index: usize,          // Индекс чанка в документе
     pub total_chunks: usize,         // Всего чанков в документе
     pub section_index: usize,        // Индекс секции
@@ -77,7 +110,7 @@ pub struct QdrantManager
 {
     client: Qdrant,
     config: QdrantConfig,
-    embedding_client: LongContextEmbedder, // Ваш клиент для эмбеддингов
+    embedding_client: Arc<LongContextEmbedder>, // Ваш клиент для эмбеддингов
 }
 
 impl QdrantManager 
@@ -91,7 +124,7 @@ impl QdrantManager
         {
             client,
             config,
-            embedding_client,
+            embedding_client: Arc::new(embedding_client),
         })
     }
     
@@ -198,32 +231,11 @@ impl QdrantManager
         vector: Vec<f32>,
         embedding_text: &str,
     ) -> Result<QdrantPoint> {
-        let id = Uuid::new_v4().to_string();
-        
-        Ok(QdrantPoint {
-            id,
-            vector,
-            payload: QdrantPayload {
-                text: chunk.content.clone(),
-                embedding_text: embedding_text.to_string(),
-                document_uri: chunk.document_uri.clone(),
-                document_title: chunk.document_title.clone(),
-                section_article: chunk.section_article.clone(),
-                chunk_index: chunk.metadata.chunk_index,
-                total_chunks: chunk.metadata.total_chunks,
-                section_index: chunk.metadata.section_index,
-                char_count: chunk.metadata.char_count,
-                is_overlap: chunk.metadata.is_overlap,
-                metadata: serde_json::json!({
-                    "char_count": chunk.metadata.char_count,
-                    "token_count": chunk.metadata.token_count,
-                    "is_overlap": chunk.metadata.is_overlap,
-                }),
-            },
-        })
+        Ok(QdrantPoint::from_chunk(chunk, vector, embedding_text))
     }
     
-    /// Поиск по семантическому сходству
+    /// Поиск по семантическому сходству  
+    /// документы утратившие силу исключаются, если в фильтре не указано `include_repealed`
     pub async fn semantic_search(
         &self,
         query: &str,
@@ -249,10 +261,8 @@ impl QdrantManager
             ..Default::default()
         };
         
-        // Добавляем фильтры, если есть
-        if let Some(filter) = filter {
-            search_request.filter = Some(filter.into());
-        }
+        // Добавляем фильтры (по умолчанию отсекаются утратившие силу документы)
+        search_request.filter = Some(filter.unwrap_or_default().into());
         
         // Выполняем поиск
         let search_results = self.client
@@ -269,6 +279,48 @@ impl QdrantManager
         Ok(results)
     }
     
+    /// Семантический поиск с пересортировкой кандидатов по векторам токенов (ColBERT, MaxSim)
+    /// по плотному вектору берется `candidates` результатов, из них после пересортировки остается `limit`
+    pub async fn colbert_search(
+        &self,
+        query: &str,
+        limit: usize,
+        candidates: usize,
+        filter: Option<SearchFilter>,
+    ) -> Result<Vec<SearchResult>> {
+        let results = self.semantic_search(query, candidates.max(limit), filter).await?;
+        let texts: Vec<&str> = results.iter().map(|r| r.payload.text.as_str()).collect();
+        let scores = self.embedding_client.rerank_colbert(query, &texts).await?;
+        let mut reranked = Vec::with_capacity(limit);
+        for (i, score) in scores.into_iter().take(limit) {
+            let mut result = results[i].clone();
+            result.score = score;
+            reranked.push(result);
+        }
+        Ok(reranked)
+    }
+
+    /// Семантический поиск с пересортировкой кандидатов кросс-энкодером
+    /// по плотному вектору берется `candidates` результатов, `reranker` оценивает каждую пару (запрос, текст) и оставляет `limit` лучших
+    pub async fn reranked_search(
+        &self,
+        reranker: &Arc<Reranker>,
+        query: &str,
+        limit: usize,
+        candidates: usize,
+        filter: Option<SearchFilter>,
+    ) -> Result<Vec<SearchResult>> {
+        let results = self.semantic_search(query, candidates.max(limit), filter).await?;
+        let texts: Vec<String> = results.iter().map(|r| r.payload.text.clone()).collect();
+        let mut reranked = Vec::with_capacity(limit);
+        for (i, score) in reranker.clone().rerank_blocking(query.to_owned(), texts, limit).await? {
+            let mut result = results[i].clone();
+            result.score = score;
+            reranked.push(result);
+        }
+        Ok(reranked)
+    }
+
     /// Поиск с гибридным подходом (семантический + ключевые слова)
     pub async fn hybrid_search(
         &self,
@@ -328,10 +380,10 @@ impl QdrantManager
         }
         
         // Если есть дополнительные фильтры
+        let filter = filter.unwrap_or_default();
+        let must_not = filter.exclusions();
         let mut all_conditions = conditions;
-        if let Some(filter) = filter {
-            all_conditions.extend(filter.conditions);
-        }
+        all_conditions.extend(filter.conditions);
         
         // Выполняем поиск
         let search_request = SearchPoints {
@@ -339,6 +391,7 @@ impl QdrantManager
             vector: vec![0.0; self.config.vector_size], // Пустой вектор для keyword search
             filter: Some(Filter {
                 should: all_conditions,
+                must_not,
                 ..Default::default()
             }),
             limit: limit as u64,
@@ -417,7 +470,8 @@ impl QdrantManager
         limit: Option<usize>,
     ) -> Result<Vec<SearchResult>> {
         let filter = SearchFilter::new()
-            .add_exact_match("document_uri", document_uri);
+            .add_exact_match("document_uri", document_uri)
+            .include_repealed();
         
         // Используем пустой вектор для получения всех точек
         let search_request = SearchPoints {
@@ -447,7 +501,8 @@ impl QdrantManager
     /// Удаление документа из индекса
     pub async fn delete_document(&self, document_uri: &str) -> Result<usize> {
         let filter = SearchFilter::new()
-            .add_exact_match("document_uri", document_uri);
+            .add_exact_match("document_uri", document_uri)
+            .include_repealed();
         
         let delete_request = qdrant_client::qdrant::DeletePoints {
             collection_name: self.config.collection_name.clone(),
@@ -474,15 +529,42 @@ impl QdrantManager
 #[derive(Debug, Clone, Default)]
 pub struct SearchFilter {
     conditions: Vec<Condition>,
+    // Искать также по документам, утратившим силу
+    include_repealed: bool,
 }
 
 impl SearchFilter {
     pub fn new() -> Self {
-        Self { conditions: Vec::new() }
+        Self { conditions: Vec::new(), include_repealed: false }
+    }
+    
+    /// Не исключать из выдачи документы, утратившие силу
+    pub fn include_repealed(mut self) -> Self {
+        self.include_repealed = true;
+        self
+    }
+    
+    /// Только чанки из приложения `number` к документу `document_uri`
+    pub fn appendix(self, document_uri: &str, number: &str) -> Self {
+        self.add_exact_match("document_uri", document_uri)
+            .add_exact_match("appendix", number)
+    }
+    
+    /// Условия исключения из выдачи (must_not)
+    fn exclusions(&self) -> Vec<Condition> {
+        if self.include_repealed {
+            return Vec::new();
+        }
+        vec![Self::keyword_condition("document_state", DocumentState::Repealed.as_str())]
     }
     
     pub fn add_exact_match(mut self, key: &str, value: &str) -> Self {
-        self.conditions.push(Condition {
+        self.conditions.push(Self::keyword_condition(key, value));
+        self
+    }
+    
+    fn keyword_condition(key: &str, value: &str) -> Condition {
+        Condition {
             condition_one_of: Some(
                 qdrant_client::qdrant::condition::ConditionOneOf::Field(
                     FieldCondition {
@@ -496,8 +578,7 @@ impl SearchFilter {
                     }
                 )
             ),
-        });
-        self
+        }
     }
     
     pub fn add_range(mut self, key: &str, gt: Option<f64>, lt: Option<f64>) -> Self {
@@ -523,6 +604,7 @@ impl SearchFilter {
 impl From<SearchFilter> for Filter {
     fn from(f: SearchFilter) -> Self {
         Filter {
+            must_not: f.exclusions(),
             must: f.conditions,
             ..Default::default()
         }
@@ -546,13 +628,16 @@ impl SearchResult {
             embedding_text: String::new(),
             document_uri: String::new(),
             document_title: Vec::new(),
+            document_number: String::new(),
+            document_sign_date: String::new(),
+            document_state: DocumentState::default(),
             section_article: None,
+            appendix: None,
             chunk_index: 0,
             total_chunks: 0,
             section_index: 0,
             char_count: 0,
             is_overlap: false,
-            metadata: serde_json::Value::Null,
         });
         
         Self {
@@ -576,4 +661,42 @@ impl From<&QdrantPoint> for PointStruct {
             payload,
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests
+{
+    use qdrant_client::qdrant::PointStruct;
+    use systema_client::DocumentState;
+    use crate::chunks::{Chunk, ChunkMetadata};
+    use super::QdrantPoint;
+
+    #[test]
+    fn test_appendix_point()
+    {
+        let chunk = Chunk {
+            document_uri: "http://pravo.gov.ru/proxy/ips/?docbody=&nd=602765432".to_owned(),
+            document_title: vec!["О внесении изменений".to_owned()],
+            document_number: "287-ФЗ".to_owned(),
+            document_sign_date: "2025-07-31".to_owned(),
+            document_state: DocumentState::InForce,
+            section_article: None,
+            appendix: Some("2".to_owned()),
+            content: "1. Товар".to_owned(),
+            metadata: ChunkMetadata {
+                chunk_index: 3,
+                total_chunks: 5,
+                section_index: 1,
+                char_count: 8,
+                token_count: None,
+                is_overlap: false,
+            },
+        };
+        let point = QdrantPoint::from_chunk(&chunk, vec![0.0; 4], "1. Товар");
+        assert_eq!(point.payload.appendix.as_deref(), Some("2"));
+        assert_eq!(point.payload.document_number, "287-ФЗ");
+        let point: PointStruct = (&point).into();
+        assert!(point.payload.contains_key("appendix"));
+        assert!(point.payload.contains_key("document_uri"));
+    }
+}
@@ -0,0 +1,174 @@
+use std::sync::Arc;
+use candle_core::{Device, Tensor};
+use candle_transformers::models::{bert::DTYPE, xlm_roberta::{Config, XLMRobertaForSequenceClassification}};
+use embedding::{ModelFiles, ModelSource};
+use tokenizers::{PaddingParams, Tokenizer, TruncationParams};
+use tracing::info;
+
+use crate::{error::{Error, Result}, model::LongContextModel};
+
+/// Кросс-энкодер (bge-reranker-v2-m3): запрос и текст кодируются вместе,
+/// голова классификации выдает оценку релевантности текста запросу
+pub struct Reranker
+{
+    model_type: LongContextModel,
+    tokenizer: Tokenizer,
+    device: Device,
+    model: XLMRobertaForSequenceClassification,
+    batch_size: usize,
+}
+
+impl Reranker
+{
+    pub fn new(model_type: LongContextModel) -> Result<Self>
+    {
+        let model_name = model_type.model_name();
+        info!("Loading reranker: {}", model_name);
+        let load_error = |e: Box<dyn std::error::Error + Send + Sync>| Error::ModelLoadError
+        {
+            model: model_name.to_string(),
+            source: e,
+        };
+        let files = ModelFiles::resolve_local(&ModelSource::new(model_name))
+            .map_err(|e| load_error(e.into()))?
+            .ok_or_else(|| load_error("файлы модели не найдены в кеше hf-hub".into()))?;
+        let device = Device::cuda_if_available(0).unwrap_or(Device::Cpu);
+
+        let mut tokenizer = Tokenizer::from_file(&files.tokenizer).map_err(load_error)?;
+        tokenizer.with_padding(Some(PaddingParams
+        {
+            strategy: tokenizers::PaddingStrategy::BatchLongest,
+            ..Default::default()
+        }));
+        // пары длиннее контекста модели обрезаются с конца текста, запрос остается целым
+        tokenizer.with_truncation(Some(TruncationParams
+        {
+            max_length: model_type.max_tokens(),
+            strategy: tokenizers::TruncationStrategy::OnlySecond,
+            ..Default::default()
+        })).map_err(load_error)?;
+
+        let config = std::fs::read_to_string(&files.config).map_err(|e| load_error(e.into()))?;
+        let config: Config = serde_json::from_str(&config).map_err(|e| load_error(e.into()))?;
+        let vb = files.weights.var_builder(DTYPE, &device).map_err(|e| load_error(e.into()))?;
+        // у bge-reranker одна метка - логит релевантности
+        let model = XLMRobertaForSequenceClassification::new(1, &config, vb)
+            .map_err(|e| load_error(e.into()))?;
+        Ok(Self
+        {
+            model_type,
+            tokenizer,
+            device,
+            model,
+            batch_size: 8,
+        })
+    }
+
+    /// Сколько пар (запрос, текст) прогоняется через модель за раз
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self
+    {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Оценки релевантности текстов запросу от 0 до 1 (сигмоида логита), в порядке текстов
+    pub fn score(&self, query: &str, texts: &[&str]) -> Result<Vec<f32>>
+    {
+        let mut scores = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.batch_size)
+        {
+            let pairs: Vec<(String, String)> = batch.iter().map(|t| (query.to_owned(), t.to_string())).collect();
+            let encodings = self.tokenizer
+                .encode_batch(pairs, true)
+                .map_err(|e| Error::TokenizationError
+                {
+                    text: query.to_string(),
+                    source: e,
+                })?;
+            let tensor = |values: fn(&tokenizers::Encoding) -> &[u32]| -> Result<Tensor>
+            {
+                let rows = encodings.iter()
+                    .map(|e| Tensor::new(values(e), &self.device))
+                    .collect::<candle_core::Result<Vec<_>>>()?;
+                Ok(Tensor::stack(&rows, 0)?)
+            };
+            let input_ids = tensor(|e| e.get_ids())?;
+            let attention_mask = tensor(|e| e.get_attention_mask())?;
+            let token_type_ids = input_ids.zeros_like()?;
+            let logits = self.model
+                .forward(&input_ids, &attention_mask, &token_type_ids)
+                .map_err(|e| Error::InferenceError { source: e })?;
+            let batch_scores: Vec<f32> = candle_nn::ops::sigmoid(&logits.to_dtype(candle_core::DType::F32)?)?
+                .flatten_all()?
+                .to_vec1()?;
+            scores.extend(batch_scores);
+        }
+        Ok(scores)
+    }
+
+    /// Индексы текстов и их оценки, от самого релевантного, не больше `top_n`
+    pub fn rerank(&self, query: &str, texts: &[&str], top_n: usize) -> Result<Vec<(usize, f32)>>
+    {
+        info!("reranking {} texts with {}", texts.len(), self.model_type.model_name());
+        Ok(ranked(self.score(query, texts)?, top_n))
+    }
+
+    /// `rerank` в пуле блокирующих задач tokio, прогон модели не должен занимать поток рантайма
+    pub async fn rerank_blocking(self: Arc<Self>, query: String, texts: Vec<String>, top_n: usize) -> Result<Vec<(usize, f32)>>
+    {
+        tokio::task::spawn_blocking(move ||
+        {
+            let texts: Vec<&str> = texts.iter().map(|t| t.as_str()).collect();
+            self.rerank(&query, &texts, top_n)
+        }).await?
+    }
+}
+
+fn ranked(scores: Vec<f32>, top_n: usize) -> Vec<(usize, f32)>
+{
+    let mut ranked: Vec<(usize, f32)> = scores.into_iter().enumerate().collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked.truncate(top_n);
+    ranked
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::sync::Arc;
+    use crate::model::LongContextModel;
+    use super::Reranker;
+
+    /// нужна bge-reranker-v2-m3 в кеше hf-hub
+    #[tokio::test]
+    #[ignore]
+    async fn test_rerank_model()
+    {
+        let reranker = Arc::new(Reranker::new(LongContextModel::BgeReranker).unwrap().with_batch_size(2));
+        let query = "в какой срок уплачивается налог на имущество физических лиц";
+        let texts = [
+            "Граждане Российской Федерации имеют право на отдых.",
+            "Налог подлежит уплате налогоплательщиками в срок не позднее 1 декабря года, следующего за истекшим налоговым периодом.",
+            "Федеральный закон вступает в силу со дня его официального опубликования.",
+        ];
+        let scores = reranker.score(query, &texts).unwrap();
+        assert_eq!(scores.len(), texts.len());
+        assert!(scores.iter().all(|s| (0.0..=1.0).contains(s)));
+        assert!(scores[1] > scores[0] && scores[1] > scores[2]);
+        let ranked = reranker.clone()
+            .rerank_blocking(query.to_owned(), texts.iter().map(|t| t.to_string()).collect(), 2)
+            .await
+            .unwrap();
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].0, 1);
+        assert!(ranked[0].1 >= ranked[1].1);
+    }
+
+    #[test]
+    fn test_ranked()
+    {
+        let ranked = super::ranked(vec![0.1, 0.9, 0.5, 0.7], 3);
+        assert_eq!(ranked, vec![(1, 0.9), (3, 0.7), (2, 0.5)]);
+        assert!(super::ranked(Vec::new(), 3).is_empty());
+    }
+}
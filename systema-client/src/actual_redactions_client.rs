@@ -7,12 +7,14 @@ use serde::{ser::{SerializeMap, SerializeSeq}, Serialize, Serializer};
 use serde_json::json;
 use tracing::{info, warn};
 //use serde_json::json;
-use utilites::{http::{Bytes, HeaderName, HyperClient, StatusCode, Uri, ACCEPT, ACCEPT_ENCODING, ACCEPT_LANGUAGE, HOST, REFERER, UPGRADE_INSECURE_REQUESTS, USER_AGENT}, Date};
-use crate::{Error, Result, encoding::encode, models::{Content, Contents, Redaction, SystemaDocumentCard}, search_attributes::{DocumentKind, SearchAttributes}};
+use hyper::StatusCode;
+use utilites::Date;
+use crate::{Error, Result, config::ClientConfig, encoding::encode, http::{self, Response}, metadata::DocumentMetadata, models::{Content, Contents, DocumentState, ExtendedRedaction, Redaction, Redactions, SystemaDocumentCard}, search_attributes::{DocumentKind, SearchAttributes, TextScope}};
 
 //static CLEAR_ID: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"\s?id=["]p\d{1,}["]"#).unwrap());
+///страница документа на портале опубликования по номеру электронного опубликования
+const PUBLICATION_URL: &str = "http://publication.pravo.gov.ru/Document/View/";
 static CLEAR_ED: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"\s?class=["]ed[x]?["]"#).unwrap());
-static API_EBPI_URL: &str = "http://actual.pravo.gov.ru:8000/api/ebpi";
 #[derive(Serialize, Debug, Copy, Clone)]
 /// Какой то тип переменной для получения редакции документа из конкретного источника  
 /// (пишу как написанго в инструкии к исходникам, хз что это)
@@ -38,76 +40,69 @@ impl Display for RedactionTtl
 }
 
 
+///Клиент api актуальных редакций, адреса, прокси и таймауты берутся из `ClientConfig`
+#[derive(Clone, Default)]
 pub struct ActualRedactionsClient
 {
-    
+    config: ClientConfig
 }
 impl ActualRedactionsClient
 {
-    fn client() -> HyperClient
+    pub fn new(config: ClientConfig) -> Self
     {
-        HyperClient::new_with_timeout(API_EBPI_URL.parse().unwrap(), 150, 950, 10)
-            .with_headers(Self::headers())
+        Self { config }
     }
-    fn headers() -> Vec<(HeaderName, String)>
+    ///Запрос к api, размер ответа ограничен `max_body_size` еще при чтении
+    async fn get(&self, path: &str, params: &[(&str, &str)]) -> Result<Response>
     {
-        let mut h= Vec::new();
-        h.push((HOST, "pravo.gov.ru".to_owned()));
-        h.push((USER_AGENT, "Mozilla/5.0 (X11; Linux x86_64; rv:127.0) Gecko/20100101 Firefox/127.0".to_owned()));
-        h.push((ACCEPT, "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,*/*;q=0.8".to_owned()));
-        h.push((ACCEPT_ENCODING, "gzip, deflate".to_owned()));
-        h.push((ACCEPT_LANGUAGE, "ru-RU,ru;q=0.8,en-US;q=0.5,en;q=0.3".to_owned()));
-        h.push((REFERER, "http:://pravo.gov.ru".to_owned()));
-        h.push((UPGRADE_INSECURE_REQUESTS, "1".to_owned()));
-        h
+        let uri = http::uri(&self.config.ebpi_url, path, params)?;
+        info!("processing request: {:?}", &uri);
+        http::get(&self.config, &uri).await
     }
 
     ///Проверка что пришел код 200 на запрос
-    fn code_error_check(response: (StatusCode, Bytes)) -> Result<Bytes>
+    fn code_error_check(&self, response: Response) -> Result<hyper::body::Bytes>
     {
-        if response.0 != utilites::http::StatusCode::OK
+        if response.status != StatusCode::OK
         {
-            let e = ["Сервер ответил кодом ", response.0.as_str(), " ожидался код 200"].concat();
+            let e = ["Сервер ответил кодом ", response.status.as_str(), " ожидался код 200"].concat();
             warn!("{}", &e);
             return Err(Error::ApiError(e));
         }
         else 
         {
-            Ok(response.1)
+            Ok(response.body)
         }
     }
 
-    pub async fn get_redactions_by_eo_number(eo: &str, ttl: RedactionTtl) -> Result<Vec<super::models::ExtendedRedaction>>
+    pub async fn get_redactions_by_eo_number(&self, eo: &str, ttl: RedactionTtl) -> Result<Redactions>
     {
         let params = json!({"pnum": eo, "ttl": ttl as u8}).to_string();
-        Self::get_redactions(params).await
+        self.get_redactions(params).await
     }
-    pub async fn get_redactions_by_hash(hash: &str, ttl: RedactionTtl) -> Result<Vec<super::models::ExtendedRedaction>>
+    pub async fn get_redactions_by_hash(&self, hash: &str, ttl: RedactionTtl) -> Result<Redactions>
     {
         let params = json!({"hash": hash, "ttl": ttl as u8}).to_string();
-        Self::get_redactions(params).await
+        self.get_redactions(params).await
     }
-    async fn get_redactions(params: String) -> Result<Vec<super::models::ExtendedRedaction>>
+    async fn get_redactions(&self, params: String) -> Result<Redactions>
     {
-        let client = Self::client().with_path("redactions");
-        info!("processing request: {:?} with params: {:?}", client.get_uri(), &[("bpa", "ebpi"), ("t", &params)]);
-        let result = client.get_with_params(&[("bpa", "ebpi"),("t", &params)]).await?;
-        let value = Self::code_error_check(result)?;
+        let result = self.get("redactions", &[("bpa", "ebpi"),("t", &params)]).await?;
+        let value = self.code_error_check(result)?;
         let redactions: super::models::RedactionsResponse = serde_json::from_slice(&value)?;
         let red_count = redactions.redactions.len();
         if red_count == 0
         {
-            return Err(Error::ApiError(["По запросу", &client.get_uri().to_string() , "не найдено ни одного документа"].concat()));
+            return Err(Error::ApiError(["По запросу ", &params, " не найдено ни одного документа"].concat()));
         }
+        let hash = redactions.hash;
         let redactions: Vec<super::models::ExtendedRedaction> = redactions.redactions.into_iter().map(|r| r.into()).collect();
-        Ok(redactions)
+        Ok(Redactions::new(redactions).with_hash(hash))
     }
-    async fn get_contents(redaction_id: &u32) -> Result<super::models::Contents>
+    async fn get_contents(&self, redaction_id: &u32) -> Result<super::models::Contents>
     {
-        let client = Self::client().with_path("getcontent");
-        info!("processing request: {:?} with params: {:?}", client.get_uri(), &[("bpa", "ebpi"), ("rdk", &redaction_id.to_string())]);
-        let result = client.get_with_params(&[("bpa", "ebpi"),("rdk", &redaction_id.to_string())]).await?;
-        let value = Self::code_error_check(result)?;
+        let result = self.get("getcontent", &[("bpa", "ebpi"),("rdk", &redaction_id.to_string())]).await?;
+        let value = self.code_error_check(result)?;
         let contents: super::models::Contents = serde_json::from_slice(&value)?;
         Ok(contents)
     }
@@ -125,16 +120,15 @@ impl ActualRedactionsClient
     //     let url = ["/redtext?t=",  &redaction_id.to_string(), "&ttl=", &ttl.to_string()].concat().parse().unwrap();
     //     url
     // }
-    pub async fn get_document_html(redaction_id: &u32, source: RedactionTtl) -> Result<String>
+    pub async fn get_document_html(&self, redaction_id: &u32, source: RedactionTtl) -> Result<String>
     {
-        let client = Self::client().with_path("redtext");
-        let result = client.get_with_params(
+        let result = self.get("redtext",
         &[
             ("bpa", "ebpi"),
             ("t", &redaction_id.to_string()),
             ("ttl", &source.to_string())
         ]).await?;
-        let value = Self::code_error_check(result)?;
+        let value = self.code_error_check(result)?;
         let text_result: super::models::SystemaTextResponse = serde_json::from_slice(&value)?;
         if text_result.error.is_some()
         {
@@ -150,18 +144,24 @@ impl ActualRedactionsClient
         }
     }
 
-    pub async fn get_clear_document_html(redaction_id: &u32, source: RedactionTtl) -> Result<String>
+    pub async fn get_clear_document_html(&self, redaction_id: &u32, source: RedactionTtl) -> Result<String>
     {
-        let text_result =  Self::get_document_html(redaction_id, source).await?;
-        let red_page = Html::parse_document(&text_result);
+        let text_result =  self.get_document_html(redaction_id, source).await?;
+        Self::clear_document_html(&text_result)
+            .map_err(|e| Error::ApiError([&e.to_string(), " редакция ", &redaction_id.to_string()].concat()))
+    }
+    ///Очистка html редакции (`redtext`) от служебной разметки: `span.mark`, `p.F`, `p.A`, `label`, `&nbsp;`, классов `ed`/`edx`
+    /// работает и с сохраненными ранее файлами, поэтому без запросов к api
+    pub fn clear_document_html(html: &str) -> Result<String>
+    {
+        let red_page = Html::parse_document(html);
         let selector = Selector::parse(r#"body"#).unwrap();
         let mark_selector = Selector::parse(r#"span.mark"#).unwrap();
-        let markx_selector = Selector::parse(r#"span.markx"#).unwrap();
         let class_f_selector = Selector::parse(r#"p.F"#).unwrap();
         let class_a_selector = Selector::parse(r#"p.A"#).unwrap();
         let label_selector = Selector::parse(r#"label"#).unwrap();
         let mut ids: Vec<_> = red_page.select(&mark_selector).map(|m| m.id()).collect();
-        ids.extend( red_page.select(&markx_selector).map(|m| m.id()));
+        //аннотации `span.markx` оставляем, из них узнаем какой документ изменил или отменил узел
         ids.extend(red_page.select(&class_f_selector).map(|m| m.id()));
         ids.extend( red_page.select(&class_a_selector).map(|m| m.id()));
         ids.extend(red_page.select(&label_selector).map(|m| m.id()));
@@ -172,29 +172,46 @@ impl ActualRedactionsClient
         }
         if let Some(txt) = red_page.0.borrow().select(&selector).next().and_then(|e| Some(e.inner_html()))
         {
-            let txt = txt.replace("&nbsp;", " ");
+            let txt = txt.replace("&nbsp;", "\u{a0}");
             //let txt = CLEAR_ID.replace_all(&txt, "");
             let txt = CLEAR_ED.replace_all(&txt, "");
             Ok(txt.into_owned())
         }
         else 
         {
-            Err(Error::ApiError(["Ошибка извлечения тела документа из редакции ", &redaction_id.to_string()].concat()))
+            Err(Error::api_error("Ошибка извлечения тела документа"))
         }
     }
 
     /// http://actual.pravo.gov.ru:8000/api/ebpi/attrsearch/?q=[{"AttrId":5,"AttrMode":0,"DateFrom":"20240101","DateTo":"20240620"},{"AttrId":999,"AttrMode":1,"Words":[50,"-date","20220701",0,1]}]
     /// только кавычки в эскейпе -> %22
-    pub async fn search_by_params(date_from: Option<Date>, date_to: Date, kinds: &[super::search_attributes::DocumentKind], pages: u32, number: Option<&str>) -> Result<Vec<SystemaDocumentCard>>
+    pub async fn search_by_params(&self, date_from: Option<Date>, date_to: Date, kinds: &[super::search_attributes::DocumentKind], pages: u32, number: Option<&str>) -> Result<Vec<SystemaDocumentCard>>
+    {
+        let (docs, _) = self.search_page(date_from, date_to, kinds, pages, number).await?;
+        Ok(docs)
+    }
+    ///То же что и `search_by_params` но дополнительно возвращает общее количество найденных документов (`docscount`)  
+    /// если количество больше чем вернулось карточек значит в запрос попали не все документы
+    pub async fn search_page(&self, date_from: Option<Date>, date_to: Date, kinds: &[super::search_attributes::DocumentKind], pages: u32, number: Option<&str>) -> Result<(Vec<SystemaDocumentCard>, u32)>
     {
         let v = SearchAttributes::get_search_attributes_vec(date_from, date_to, kinds, pages, number);
-        let attrs = serde_json::to_string(&v).unwrap();
-        let client = Self::client().with_path("attrsearch");
-        info!("processing request: {:?} with params: {:?}", client.get_uri(), &[("bpa", "ebpi"), ("q", &attrs)]);
-        let response = client.get_with_params(&[("bpa", "ebpi"), ("q", &attrs)]).await?;
+        self.attr_search(&v).await
+    }
+    ///Полнотекстовый поиск (или поиск по словам названия), карточки возвращаются в порядке релевантности
+    pub async fn search_text(&self, query: &str, scope: TextScope, date_from: Option<Date>, date_to: Date, kinds: &[super::search_attributes::DocumentKind], pages: u32) -> Result<Vec<SearchHit>>
+    {
+        let v = SearchAttributes::get_text_search_attributes_vec(query, scope, date_from, date_to, kinds, pages);
+        let (docs, _) = self.attr_search(&v).await?;
+        let hits = docs.into_iter().enumerate().map(|(i, card)| SearchHit { rank: i + 1, card }).collect();
+        Ok(hits)
+    }
+    async fn attr_search(&self, attributes: &[SearchAttributes]) -> Result<(Vec<SystemaDocumentCard>, u32)>
+    {
+        let attrs = serde_json::to_string(attributes).unwrap();
+        let response = self.get("attrsearch", &[("bpa", "ebpi"), ("q", &attrs)]).await?;
        
-        let body = Self::code_error_check(response)?;
-        let uri_str = client.get_uri().to_string();
+        let body = self.code_error_check(response)?;
+        let uri_str = ["attrsearch ", &attrs].concat();
         let docs: super::models::DocumentsSearchResponse = serde_json::from_slice(&body)?;
         if docs.error.is_some()
         {
@@ -203,18 +220,18 @@ impl ActualRedactionsClient
         if docs.docscount == 0
         {
             warn!("По запросу, {uri_str}, не найдено ни одного документа");
-            return Ok(Vec::with_capacity(0));
+            return Ok((Vec::with_capacity(0), 0));
         }
-        Ok(docs.docs)
+        Ok((docs.docs, docs.docscount))
     }
     //new
     /// http://actual.pravo.gov.ru:8000/api/ebpi/attrsearch/?bpa=ebpi&q=[{"AttrId":5,"AttrMode":0,"DateTo":"20251222"},{"AttrId":4,"AttrMode":1,"IDParams":[{"Id":108,"Param":0},{"Id":107,"Param":0}]},{"AttrId":999,"AttrMode":1,"Words":[50,"type","20220701",0,1]}]
     /// http://actual.pravo.gov.ru:8000/api/ebpi/attrsearch/?bpa=ebpi&q=[{"AttrId":5,"AttrMode":0,"DateFrom":"20240101","DateTo":"20240620"},{"AttrId":999,"AttrMode":1,"Words":[50,"-date","20220701",0,1]}]
-    pub async fn search_default(date: Date, number: &str) -> Result<SystemaDocumentCard>
+    pub async fn search_default(&self, date: Date, number: &str) -> Result<SystemaDocumentCard>
     {
         let kinds = &[super::search_attributes::DocumentKind::Fz, super::search_attributes::DocumentKind::Fkz ];
         let formatted_date = date.format(utilites::DateFormat::DotDate);
-        let docs = Self::search_by_params(Some(date.clone()), date, kinds, 1, Some(number)).await?;
+        let docs = self.search_by_params(Some(date.clone()), date, kinds, 1, Some(number)).await?;
         if docs.len() > 1
         {
             return Err(Error::ApiError(["По запросу ", "№ ", number, " от ", &formatted_date, " найдено более 1(",&docs.len().to_string(),") документа, уточните запрос"  ].concat()));
@@ -226,39 +243,107 @@ impl ActualRedactionsClient
         Ok(doc)
     }
 
-    pub async fn get_document(date: Date, number: &str) -> Result<DocumentResponse>
+    pub async fn get_document(&self, date: Date, number: &str) -> Result<DocumentResponse>
     {
-        let cards = super::ActualRedactionsClient::search_default(date, number).await?;
-        let hash = cards.hash;
-        let redactions = super::ActualRedactionsClient::get_redactions_by_hash(&hash, RedactionTtl::Actual).await?;
-        let actual = redactions.into_iter().filter(|f| f.is_actual).next()
-            .ok_or(Error::ApiError(format!("Актуальная редакция для документа {} не найдена", cards.doc_id)))?;
-        let contents = super::ActualRedactionsClient::get_contents(&actual.id).await?;
-        let document = super::ActualRedactionsClient::get_clear_document_html(&actual.id, RedactionTtl::Actual).await.unwrap();
-        let document = Html::parse_document(&document);
+        let card = self.search_default(date, number).await?;
+        self.get_document_by_card(card).await
+    }
+    ///Получение актуальной редакции документа по карточке найденной через `search_by_params`
+    pub async fn get_document_by_card(&self, card: SystemaDocumentCard) -> Result<DocumentResponse>
+    {
+        let redactions = self.get_redactions_by_hash(&card.hash, RedactionTtl::Actual).await?;
+        let actual = redactions.actual()
+            .ok_or(Error::ApiError(format!("Актуальная редакция для документа {} не найдена", card.doc_id)))?;
+        self.get_document_redaction(&card, actual).await
+    }
+    ///Актуальная редакция документа когда карточки нет, только список редакций (поиск по хешу или номеру опубликования)
+    /// номер и дата подписания берутся из шапки и подписи текста, по ним ищется карточка с названием и ссылкой на опубликование
+    /// если карточку найти не удалось ссылка строится по номеру опубликования `eo`
+    pub async fn get_document_by_redactions(&self, redactions: &Redactions, eo: Option<&str>) -> Result<DocumentResponse>
+    {
+        let redaction = redactions.actual().or(redactions.last())
+            .ok_or(Error::ApiError(["У документа ", redactions.hash(), " нет редакций"].concat()))?;
+        let contents = self.get_contents(&redaction.id).await?;
+        let html = self.get_document_html(&redaction.id, RedactionTtl::Actual).await?;
+        let metadata = DocumentMetadata::from_html(&html);
+        let sign_date = metadata.sign_date
+            .ok_or(Error::ContentError(["Не удалось определить дату подписания документа ", redactions.hash()].concat()))?;
+        let number = metadata.number.unwrap_or_default();
+        let card = if number.is_empty()
+        {
+            None
+        }
+        else
+        {
+            match self.search_default(sign_date.clone(), &number).await
+            {
+                Ok(card) => Some(card),
+                Err(e) =>
+                {
+                    warn!("карточка документа {} от {} не найдена: {}", &number, sign_date.format(utilites::DateFormat::DotDate), e);
+                    None
+                }
+            }
+        };
+        let (name, publication_url) = match card
+        {
+            Some(card) => (card.name, card.publication_url),
+            None => (metadata.title.unwrap_or_default(), eo.map(|eo| [PUBLICATION_URL, eo].concat()).unwrap_or_default())
+        };
+        Ok(DocumentResponse
+        {
+            html,
+            contents,
+            name,
+            number,
+            sign_date,
+            publication_url,
+            state: redaction.state,
+            hash: redactions.hash().to_owned(),
+            redaction_id: redaction.id
+        })
+    }
+    ///Текст и содержание конкретной редакции документа
+    pub async fn get_document_redaction(&self, card: &SystemaDocumentCard, redaction: &ExtendedRedaction) -> Result<DocumentResponse>
+    {
+        let contents = self.get_contents(&redaction.id).await?;
+        //служебная разметка убирается при потоковом разборе параграфов, лишний раз большой документ не перестраиваем
+        let document = self.get_document_html(&redaction.id, RedactionTtl::Actual).await?;
         let response = DocumentResponse
         {
             html: document,
             contents,
-            name: cards.name,
-            number: cards.number,
-            sign_date: cards.sign_date,
-            publication_url: cards.publication_url,
-            hash,
-            redaction_id: actual.id
+            name: card.name.clone(),
+            number: card.number.clone(),
+            sign_date: card.sign_date.clone(),
+            publication_url: card.publication_url.clone(),
+            state: card.doc_state.as_str().into(),
+            hash: card.hash.clone(),
+            redaction_id: redaction.id
         };
         Ok(response)
     }
 }
 
+///Карточка документа найденная текстовым поиском
+#[derive(Debug, Clone)]
+pub struct SearchHit
+{
+    ///позиция в выдаче, начиная с 1 (чем меньше тем релевантнее)
+    pub rank: usize,
+    pub card: SystemaDocumentCard
+}
+
 pub struct DocumentResponse
 {
-    pub html: Html,
+    ///html редакции, служебная разметка (`span.mark`, `p.F`, `p.A` ...) при построении узлов пропускается (см. `stream::paragraphs`)
+    pub html: String,
     pub contents: Contents,
     pub name: String,
     pub number: String,
     pub sign_date: Date,
     pub publication_url: String,
+    pub state: DocumentState,
     pub hash: String,
     pub redaction_id: u32
 }
@@ -301,11 +386,46 @@ mod tests
         info!("{}", s);
     }
 
+    #[test]
+    fn test_text_search_ser()
+    {
+        logger::init();
+        let v = super::SearchAttributes::get_text_search_attributes_vec("налоговая тайна", super::TextScope::FullText, None, Date::new_date(22, 12, 2025), &[super::super::search_attributes::DocumentKind::Fz], 20);
+        let s = serde_json::to_value(&v).unwrap();
+        let test_data = json!([{"AttrId": 5,"AttrMode": 0,"DateTo": "20251222"},{"AttrId": 4,"AttrMode": 1,"IDParams": [{"Id": 108,"Param": 0}]},{"AttrId": 0,"AttrMode": 8,"Words": ["налоговая тайна"]},{"AttrId": 999,"AttrMode": 1,"Words": [20,"-rank","20220701",0,1]}]);
+        assert_eq!(s, test_data);
+    }
+
+    #[tokio::test]
+    async fn test_text_search_request()
+    {
+        logger::init();
+        let hits = super::ActualRedactionsClient::default().search_text("налоговая тайна", super::TextScope::FullText, None, Date::now(), &[super::super::search_attributes::DocumentKind::Fz, super::super::search_attributes::DocumentKind::Fkz], 10).await.unwrap();
+        for hit in &hits
+        {
+            info!("{}: {}", hit.rank, &hit.card.complex_name);
+        }
+        assert!(hits.iter().any(|h| h.card.name.contains("Налоговый кодекс")));
+    }
+
+    #[tokio::test]
+    async fn test_title_search_request()
+    {
+        logger::init();
+        let hits = super::ActualRedactionsClient::default().search_text("Об образовании в Российской Федерации", super::TextScope::Title, None, Date::now(), &[super::super::search_attributes::DocumentKind::Fz], 10).await.unwrap();
+        for hit in &hits
+        {
+            info!("{}: {}", hit.rank, &hit.card.complex_name);
+        }
+        //273-ФЗ, хеш тот же что в test_273_fz
+        assert!(hits.iter().any(|h| h.card.hash == "48c91a7c1a9416aee3ea23eef7c9aca7226cd3eedeebf94b8232532b5115b2dc"));
+    }
+
     #[tokio::test]
     async fn test_search_request()
     {
         logger::init();
-        let cards = super::ActualRedactionsClient::search_by_params(None, Date::now(), &[super::super::search_attributes::DocumentKind::Fz, super::super::search_attributes::DocumentKind::Fkz ], 1, Some("470-ФЗ")).await.unwrap();
+        let cards = super::ActualRedactionsClient::default().search_by_params(None, Date::now(), &[super::super::search_attributes::DocumentKind::Fz, super::super::search_attributes::DocumentKind::Fkz ], 1, Some("470-ФЗ")).await.unwrap();
         info!("{}", &cards[0].complex_name);
         assert_eq!(cards[0].hash, "24793801ef77005c45edd990141f89c8067dfb36af0548484059412eb35afe8e");
     }
@@ -313,7 +433,7 @@ mod tests
     async fn test_273_fz()
     {
         logger::init();
-        let cards = super::ActualRedactionsClient::search_by_params(Some(Date::new_date(29, 12, 2012)), Date::new_date(29, 12, 2012), &[super::super::search_attributes::DocumentKind::Fz, super::super::search_attributes::DocumentKind::Fkz ], 1, Some("273-ФЗ")).await.unwrap();
+        let cards = super::ActualRedactionsClient::default().search_by_params(Some(Date::new_date(29, 12, 2012)), Date::new_date(29, 12, 2012), &[super::super::search_attributes::DocumentKind::Fz, super::super::search_attributes::DocumentKind::Fkz ], 1, Some("273-ФЗ")).await.unwrap();
         //тест поиска старых законов
         info!("{}->{}", &cards[0].complex_name, &cards[0].hash);
         assert_eq!(cards[0].hash, "48c91a7c1a9416aee3ea23eef7c9aca7226cd3eedeebf94b8232532b5115b2dc");
@@ -323,7 +443,7 @@ mod tests
     async fn test_attr_search_request()
     {
         logger::init();
-        let cards = super::ActualRedactionsClient::search_by_params(Some(Date::new_date(29, 1, 2022)), Date::new_date(29, 1, 2022), &[super::super::search_attributes::DocumentKind::Fz, super::super::search_attributes::DocumentKind::Fkz ], 1, Some("573-ФЗ")).await.unwrap();
+        let cards = super::ActualRedactionsClient::default().search_by_params(Some(Date::new_date(29, 1, 2022)), Date::new_date(29, 1, 2022), &[super::super::search_attributes::DocumentKind::Fz, super::super::search_attributes::DocumentKind::Fkz ], 1, Some("573-ФЗ")).await.unwrap();
         info!("{}", &cards[0].complex_name);
         assert_eq!(cards[0].hash, "24793801ef77005c45edd990141f89c8067dfb36af0548484059412eb35afe8e");
     }
@@ -331,7 +451,7 @@ mod tests
     async fn test_search_new_law()
     {
         logger::init();
-        let cards = super::ActualRedactionsClient::search_by_params(Some(Date::new_date(22, 6, 24)), Date::now(), &[super::super::search_attributes::DocumentKind::Fz, super::super::search_attributes::DocumentKind::Fkz ], 1, Some("155-ФЗ")).await.unwrap();
+        let cards = super::ActualRedactionsClient::default().search_by_params(Some(Date::new_date(22, 6, 24)), Date::now(), &[super::super::search_attributes::DocumentKind::Fz, super::super::search_attributes::DocumentKind::Fkz ], 1, Some("155-ФЗ")).await.unwrap();
         debug!("{:?}", cards);
         //assert_eq!(cards[0].hash, "24793801ef77005c45edd990141f89c8067dfb36af0548484059412eb35afe8e");
     }
@@ -340,7 +460,7 @@ mod tests
     {
         logger::init();
         let hash = "24793801ef77005c45edd990141f89c8067dfb36af0548484059412eb35afe8e";
-        let redactions = super::ActualRedactionsClient::get_redactions_by_hash(hash, RedactionTtl::Actual).await.unwrap();
+        let redactions = super::ActualRedactionsClient::default().get_redactions_by_hash(hash, RedactionTtl::Actual).await.unwrap();
         assert_eq!(redactions[0].id, 444467);
         debug!("{:?}", redactions);
     }
@@ -349,7 +469,7 @@ mod tests
     {
         logger::init();
         let hash = "48c91a7c1a9416aee3ea23eef7c9aca7226cd3eedeebf94b8232532b5115b2dc";
-        let redactions = super::ActualRedactionsClient::get_redactions_by_hash(hash, RedactionTtl::Actual).await.unwrap();
+        let redactions = super::ActualRedactionsClient::default().get_redactions_by_hash(hash, RedactionTtl::Actual).await.unwrap();
         //assert_eq!(redactions[0].id, 444467);
         debug!("{:?}", redactions);
     }
@@ -359,8 +479,8 @@ mod tests
     {
         logger::init();
         let hash = "24793801ef77005c45edd990141f89c8067dfb36af0548484059412eb35afe8e";
-        let redactions = super::ActualRedactionsClient::get_redactions_by_hash(hash, RedactionTtl::Actual).await.unwrap();
-        let text_result = super::ActualRedactionsClient::get_document_html(&redactions[0].id, RedactionTtl::Actual).await.unwrap();
+        let redactions = super::ActualRedactionsClient::default().get_redactions_by_hash(hash, RedactionTtl::Actual).await.unwrap();
+        let text_result = super::ActualRedactionsClient::default().get_document_html(&redactions[0].id, RedactionTtl::Actual).await.unwrap();
         debug!("{}", text_result);
     }
     #[tokio::test]
@@ -368,8 +488,8 @@ mod tests
     {
         logger::init();
         let hash = "24793801ef77005c45edd990141f89c8067dfb36af0548484059412eb35afe8e";
-        let redactions = super::ActualRedactionsClient::get_redactions_by_hash(hash, RedactionTtl::Actual).await.unwrap();
-        let text_result = super::ActualRedactionsClient::get_clear_document_html(&redactions[0].id, RedactionTtl::Actual).await.unwrap();
+        let redactions = super::ActualRedactionsClient::default().get_redactions_by_hash(hash, RedactionTtl::Actual).await.unwrap();
+        let text_result = super::ActualRedactionsClient::default().get_clear_document_html(&redactions[0].id, RedactionTtl::Actual).await.unwrap();
         debug!("{}", text_result);
     }
 
@@ -377,23 +497,35 @@ mod tests
     async fn test_search_default()
     {
         logger::init();
-        let cards = super::ActualRedactionsClient::search_default(Date::new_date(29, 05, 2024), "102-ФЗ").await.unwrap();
+        let cards = super::ActualRedactionsClient::default().search_default(Date::new_date(29, 05, 2024), "102-ФЗ").await.unwrap();
         debug!("{:?}", cards);
         //assert_eq!(cards[0].hash, "24793801ef77005c45edd990141f89c8067dfb36af0548484059412eb35afe8e");
     }
 
+    #[tokio::test]
+    async fn test_get_document_by_redactions()
+    {
+        logger::init();
+        let client = super::ActualRedactionsClient::default();
+        let card = client.search_default(Date::new_date(29, 05, 2024), "102-ФЗ").await.unwrap();
+        let redactions = client.get_redactions_by_hash(&card.hash, RedactionTtl::Actual).await.unwrap();
+        let document = client.get_document_by_redactions(&redactions, None).await.unwrap();
+        assert_eq!(document.name, card.name);
+        assert_eq!(document.publication_url, card.publication_url);
+    }
+
     #[tokio::test]
     async fn test_get_document()
     {
         logger::init();
-        let cards = super::ActualRedactionsClient::search_default(Date::new_date(31, 07, 2025), "287-ФЗ").await.unwrap();
+        let cards = super::ActualRedactionsClient::default().search_default(Date::new_date(31, 07, 2025), "287-ФЗ").await.unwrap();
         let hash = cards.hash;
-        let redactions = super::ActualRedactionsClient::get_redactions_by_hash(&hash, RedactionTtl::Actual).await.unwrap();
-        let actual = redactions.into_iter().filter(|f| f.is_actual).next().unwrap();
+        let redactions = super::ActualRedactionsClient::default().get_redactions_by_hash(&hash, RedactionTtl::Actual).await.unwrap();
+        let actual = redactions.actual().unwrap();
         debug!("actual redaction {:?}", actual);
-        let contents = super::ActualRedactionsClient::get_contents(&actual.id).await.unwrap();
+        let contents = super::ActualRedactionsClient::default().get_contents(&actual.id).await.unwrap();
         debug!("content: {:?}", contents);
-        let document = super::ActualRedactionsClient::get_clear_document_html(&actual.id, RedactionTtl::Actual).await.unwrap();
+        let document = super::ActualRedactionsClient::default().get_clear_document_html(&actual.id, RedactionTtl::Actual).await.unwrap();
         debug!("{:?}", document);
         let red_page = Html::parse_document(&document);
         debug!("{:?}", red_page)
@@ -0,0 +1,191 @@
+use std::sync::LazyLock;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use utilites::Date;
+
+static ACT_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"от\s+(?<date>\d{2}[.]\d{2}[.]\d{4})\s+(года\s+)?№\s*(?<number>[\dА-Яа-яA-Za-z\-/]+)").unwrap());
+static NOTE_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*Примечани[еяй]\s*[.:]?(\s|$)").unwrap());
+static FOOTNOTE_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*[(]?[*]+[)]?\s*\S").unwrap());
+static REPEALED_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)^\s*((статья|глава|раздел|подраздел|параграф|часть|пункт)\s+[\dIVXLC.\-]+\s*[.)]?\s*)?([\dа-я]{1,3}[.)]\s*)?[(]?\s*(утратил|утратила|утратило|утратили)\s+силу").unwrap());
+
+///Вид изменения из аннотации `markx`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AmendmentKind
+{
+    ///"Дополнение частью - ...", "Дополнен статьей - ..."
+    Addition,
+    ///"В редакции Федерального закона ..."
+    Revision,
+    ///"Утратила силу - ...", "Признан утратившим силу ..."
+    Repeal,
+    ///"Действие приостановлено ..."
+    Suspension,
+    Other
+}
+impl From<&str> for AmendmentKind
+{
+    fn from(value: &str) -> Self
+    {
+        let value = value.trim_start_matches(|c: char| c == '(' || c.is_whitespace()).to_lowercase();
+        if value.starts_with("дополн")
+        {
+            Self::Addition
+        }
+        else if value.starts_with("в редакции") || value.starts_with("изложен")
+        {
+            Self::Revision
+        }
+        else if value.starts_with("утратил") || value.starts_with("признан утратившим")
+        {
+            Self::Repeal
+        }
+        else if value.contains("приостановлен")
+        {
+            Self::Suspension
+        }
+        else
+        {
+            Self::Other
+        }
+    }
+}
+
+///Документ который внес изменение
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AmendingAct
+{
+    pub date: Option<Date>,
+    ///"425-ФЗ"
+    pub number: Option<String>,
+    ///хеш документа из ссылки `gohash=`
+    pub hash: Option<String>
+}
+
+///Аннотация об изменении узла документа, например `(Дополнение частью - Федеральный закон от 28.11.2025 № 425-ФЗ)`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Amendment
+{
+    pub kind: AmendmentKind,
+    ///текст аннотации без скобок
+    pub text: String,
+    pub acts: Vec<AmendingAct>
+}
+impl Amendment
+{
+    ///Разбор текста аннотации, хеши документов передаются в порядке упоминания
+    pub fn parse(text: &str, hashes: &[String]) -> Self
+    {
+        let text = text.replace('\u{a0}', " ");
+        let text = text.trim().trim_start_matches('(').trim_end_matches(')').trim().to_owned();
+        let mut acts: Vec<AmendingAct> = ACT_RX.captures_iter(&text).map(|cpt|
+        {
+            AmendingAct
+            {
+                date: cpt.name("date").and_then(|d| Date::parse(d.as_str())),
+                number: cpt.name("number").and_then(|n| Some(n.as_str().to_owned())),
+                hash: None
+            }
+        }).collect();
+        if acts.len() == hashes.len()
+        {
+            for (act, hash) in acts.iter_mut().zip(hashes)
+            {
+                act.hash = Some(hash.clone());
+            }
+        }
+        Self
+        {
+            kind: text.as_str().into(),
+            text,
+            acts
+        }
+    }
+    ///Узел утратил силу, но отдельной аннотации нет, отменяющий документ указан прямо в тексте:
+    /// `Статья 12. Утратила силу. - Федеральный закон от 01.07.2021 № 250-ФЗ`
+    pub fn repeal_from_text(text: &str) -> Self
+    {
+        let mut amendment = Self::parse(text, &[]);
+        amendment.kind = AmendmentKind::Repeal;
+        amendment
+    }
+    pub fn is_repeal(&self) -> bool
+    {
+        self.kind == AmendmentKind::Repeal
+    }
+}
+
+///Текст узла говорит о том что он утратил силу: `Статья 12. Утратила силу.`, `2. Утратила силу.`, `а) утратил силу;`
+pub fn is_repealed_text(text: &str) -> bool
+{
+    REPEALED_RX.is_match(&text.replace('\u{a0}', " "))
+}
+
+///Начало блока примечаний: `Примечание.`, `Примечания:`, `Примечание. 1. Лицом ...`
+pub fn is_note_heading(text: &str) -> bool
+{
+    NOTE_RX.is_match(&text.replace('\u{a0}', " "))
+}
+
+///Сноска: `* Наименование в редакции ...`
+pub fn is_footnote(text: &str) -> bool
+{
+    FOOTNOTE_RX.is_match(&text.replace('\u{a0}', " "))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::{Amendment, AmendmentKind};
+
+    #[test]
+    fn test_parse_amendment()
+    {
+        let amendment = Amendment::parse(" (Дополнение частью - Федеральный закон от 28.11.2025 № 425-ФЗ)", &["9ba1e79973a0348999e09789280f0546258a12e408a60a09c52254290233fbcf".to_owned()]);
+        assert_eq!(amendment.kind, AmendmentKind::Addition);
+        assert_eq!(amendment.acts.len(), 1);
+        assert_eq!(amendment.acts[0].number.as_deref(), Some("425-ФЗ"));
+        assert!(amendment.acts[0].hash.is_some());
+        let amendment = Amendment::parse("(Утратила силу - Федеральный закон от 01.07.2021 № 250-ФЗ)", &[]);
+        assert!(amendment.is_repeal());
+        let amendment = Amendment::repeal_from_text("Статья 12. Утратила силу. - Федеральный закон от 01.07.2021 № 250-ФЗ");
+        assert!(amendment.is_repeal());
+        assert_eq!(amendment.acts[0].number.as_deref(), Some("250-ФЗ"));
+        let amendment = Amendment::parse("(В редакции федеральных законов от 29.12.2017 № 473-ФЗ; от 26.07.2019 № 232-ФЗ)", &[]);
+        assert_eq!(amendment.kind, AmendmentKind::Revision);
+        assert_eq!(amendment.acts.len(), 2);
+    }
+
+    #[test]
+    fn test_repealed_text()
+    {
+        assert!(super::is_repealed_text("Статья 12. Утратила силу. - Федеральный закон от 01.07.2021 № 250-ФЗ"));
+        assert!(super::is_repealed_text("2. Утратила силу."));
+        assert!(super::is_repealed_text("а) утратил силу;"));
+        assert!(super::is_repealed_text("Статья 5.1. (Утратила силу - Федеральный закон от 01.07.2021 № 250-ФЗ)"));
+        assert!(!super::is_repealed_text("Статья 36. Стипендии и другие денежные выплаты"));
+        assert!(!super::is_repealed_text("3. Признать утратившими силу отдельные положения"));
+    }
+
+    #[test]
+    fn test_notes()
+    {
+        assert!(super::is_note_heading("Примечание. Под хищением в статьях настоящего Кодекса понимаются ..."));
+        assert!(super::is_note_heading("Примечания:"));
+        assert!(!super::is_note_heading("Примечательно, что"));
+        assert!(super::is_footnote("* Наименование в редакции Федерального закона"));
+        assert!(!super::is_footnote("1. Настоящий Федеральный закон"));
+    }
+
+    #[test]
+    fn test_paragraph_amendments()
+    {
+        let paragraphs = crate::stream::paragraphs(r#"<p id="p165"><span class="edx">12. Установить, что</span><span class="markx"> (Дополнение частью - Федеральный закон <span class="cmd-hide" cmdprm="gohash=9ba1e79973a0348999e09789280f0546258a12e408a60a09c52254290233fbcf goparaid=p1787 goback=1">от 28.11.2025 № 425-ФЗ</span>)</span></p>"#);
+        let p = &paragraphs[0];
+        assert_eq!(p.amendments.len(), 1);
+        assert_eq!(p.amendments[0].acts[0].hash.as_deref(), Some("9ba1e79973a0348999e09789280f0546258a12e408a60a09c52254290233fbcf"));
+        assert_eq!(p.text, "12. Установить, что");
+        assert!(!p.html_without_amendments.contains("markx"));
+        let paragraphs = crate::stream::paragraphs(r#"<p id="p450"><span class="markx">(В редакции <ins>федеральных</ins><del>Федерального</del> <ins>законов</ins><del>закона</del> от 28.04.2023 № 1-ФКЗ)</span></p>"#);
+        assert_eq!(paragraphs[0].amendments[0].text, "В редакции федеральных законов от 28.04.2023 № 1-ФКЗ");
+    }
+}
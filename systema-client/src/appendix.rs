@@ -0,0 +1,114 @@
+use std::{collections::BTreeMap, sync::LazyLock};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use crate::models::ContentItem;
+
+static APPENDIX_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)^\s*приложени[ея]\s*((N|№)\s*(?<number>[\dIVX.\-]+))?\s*(к\s+.*)?$").unwrap());
+///заголовок приложения короткий, длинный параграф с таким началом это обычный текст
+const MAX_HEADING_LEN: usize = 300;
+///сколько параграфов после заголовка просматриваем в поисках названия приложения
+const TITLE_LOOKUP: usize = 5;
+
+///Приложение к документу, в содержание (`getcontent`) не входит и строится как отдельное поддерево с уровнем 0
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Appendix
+{
+    ///номер приложения, у единственного приложения без номера `1`
+    pub number: String,
+    ///"Приложение № 2 к Федеральному закону от ..."
+    pub caption: String,
+    ///"ПЕРЕЧЕНЬ ..."
+    pub title: Option<String>,
+    pub start: usize,
+    pub end: usize
+}
+impl Appendix
+{
+    pub fn contains(&self, id: usize) -> bool
+    {
+        id >= self.start && id <= self.end
+    }
+    ///Номер приложения из его заголовка "Приложение № 2 к ...", `None` если параграф не заголовок приложения
+    pub fn number_from_heading(text: &str) -> Option<String>
+    {
+        heading_number(text)
+    }
+}
+
+fn heading_number(text: &str) -> Option<String>
+{
+    if text.chars().count() > MAX_HEADING_LEN
+    {
+        return None;
+    }
+    APPENDIX_RX.captures(text).map(|c| c.name("number").map(|n| n.as_str().trim_end_matches('.').to_owned()).unwrap_or("1".to_owned()))
+}
+
+///Поиск приложений среди параграфов документа `(id, текст)`
+/// заголовок приложения должен быть вне диапазонов содержания, приложение длится до следующего приложения или до конца документа
+pub(crate) fn find_appendices(paragraphs: &[(usize, String)], content_map: &BTreeMap<usize, ContentItem>) -> Vec<Appendix>
+{
+    let in_contents = |id: usize| content_map.values().any(|c| id >= c.start && id <= c.end);
+    let mut appendices: Vec<Appendix> = Vec::new();
+    for (i, (id, text)) in paragraphs.iter().enumerate()
+    {
+        if in_contents(*id)
+        {
+            continue;
+        }
+        if let Some(number) = heading_number(text)
+        {
+            if let Some(prev) = appendices.last_mut()
+            {
+                prev.end = id - 1;
+            }
+            let title = paragraphs[i + 1..].iter()
+                .take(TITLE_LOOKUP)
+                .map(|(_, t)| t.trim())
+                .find(|t| !t.is_empty() && !t.starts_with("к ") && heading_number(t).is_none())
+                .map(|t| t.to_owned());
+            appendices.push(Appendix
+            {
+                number,
+                caption: text.split_whitespace().collect::<Vec<&str>>().join(" "),
+                title,
+                start: *id,
+                end: paragraphs.last().map(|(id, _)| *id).unwrap_or(*id)
+            });
+        }
+    }
+    appendices
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::collections::BTreeMap;
+    use crate::models::ContentItem;
+
+    #[test]
+    fn test_find_appendices()
+    {
+        let mut content_map = BTreeMap::new();
+        content_map.insert(10, ContentItem { start: 10, end: 40, caption: "$Статья 1".to_owned(), name: "статья".to_owned(), lvl: 0 });
+        let paragraphs: Vec<(usize, String)> = vec![
+            (11, "Приложение к настоящему Федеральному закону".to_owned()),
+            (41, "Президент Российской Федерации".to_owned()),
+            (50, "Приложение № 1".to_owned()),
+            (51, "к Федеральному закону от 31.07.2025 № 287-ФЗ".to_owned()),
+            (52, "ПЕРЕЧЕНЬ ТОВАРОВ".to_owned()),
+            (53, "1. Товар".to_owned()),
+            (60, "ПРИЛОЖЕНИЕ N 2".to_owned()),
+            (61, "ФОРМА".to_owned()),
+            (62, "Текст".to_owned()),
+        ];
+        let appendices = super::find_appendices(&paragraphs, &content_map);
+        assert_eq!(appendices.len(), 2);
+        assert_eq!(appendices[0].number, "1");
+        assert_eq!(appendices[0].title.as_deref(), Some("ПЕРЕЧЕНЬ ТОВАРОВ"));
+        assert_eq!((appendices[0].start, appendices[0].end), (50, 59));
+        assert_eq!(appendices[1].number, "2");
+        assert_eq!((appendices[1].start, appendices[1].end), (60, 62));
+        assert!(appendices[1].contains(61));
+    }
+}
@@ -0,0 +1,294 @@
+use std::{collections::{HashMap, HashSet}, fmt::Debug, sync::Arc};
+use serde::Serialize;
+use tokio::{sync::Semaphore, task::JoinSet};
+use tracing::{error, info, warn};
+use utilites::{Date, DateFormat};
+use crate::{SystemaClient, builder, converter::Converter, document::DocumentNodes, error::{Error, Result}, models::SystemaDocumentCard, search_attributes::DocumentKind};
+
+///Сколько карточек запрашиваем одним поисковым запросом
+const SEARCH_PAGE_SIZE: u32 = 500;
+
+///Документ который не удалось получить или обработать в пакетной загрузке
+#[derive(Debug, Serialize)]
+pub struct BatchFailure
+{
+    pub hash: String,
+    pub number: String,
+    pub sign_date: Date,
+    pub name: String,
+    pub error: String,
+}
+impl BatchFailure
+{
+    fn new(card: &SystemaDocumentCard, error: &Error) -> Self
+    {
+        Self
+        {
+            hash: card.hash.clone(),
+            number: card.number.clone(),
+            sign_date: card.sign_date.clone(),
+            name: card.name.clone(),
+            error: error.to_string()
+        }
+    }
+    ///День за который api вернул не все документы (больше `SEARCH_PAGE_SIZE`), у такой записи нет хеша и номера
+    fn shortfall(day: Date, total: u32, received: usize) -> Self
+    {
+        Self
+        {
+            hash: String::new(),
+            number: String::new(),
+            error: ["за ", &day.format(DateFormat::DotDate), " найдено ", &total.to_string(), " документов, но получено только ", &received.to_string()].concat(),
+            sign_date: day,
+            name: String::new()
+        }
+    }
+}
+
+///Итог пакетной загрузки документов
+#[derive(Debug, Serialize)]
+pub struct BatchReport
+{
+    ///всего найдено карточек за период
+    pub total: usize,
+    ///хеши успешно обработанных документов
+    pub processed: Vec<String>,
+    pub failures: Vec<BatchFailure>,
+}
+
+impl SystemaClient
+{
+    ///Все карточки документов подписанных в период с `date_from` по `date_to` включительно
+    /// период запрашивается помесячно, если за месяц api вернул не все документы то месяц запрашивается по дням
+    /// дни за которые и так получено не все только логируются, в `process_period` они попадают в `BatchReport::failures`
+    pub async fn search_period(&self, date_from: Date, date_to: Date, kinds: &[DocumentKind]) -> Result<Vec<SystemaDocumentCard>>
+    {
+        let (cards, _) = self.search_period_checked(date_from, date_to, kinds).await?;
+        Ok(cards)
+    }
+    ///Карточки за период и дни за которые api вернул не все документы
+    async fn search_period_checked(&self, date_from: Date, date_to: Date, kinds: &[DocumentKind]) -> Result<(Vec<SystemaDocumentCard>, Vec<BatchFailure>)>
+    {
+        let client = self.client();
+        let (docs, shortfalls) = search_ranges(&date_from, &date_to, |from, to|
+        {
+            let client = client.clone();
+            async move { client.search_page(Some(from), to, kinds, SEARCH_PAGE_SIZE, None).await }
+        }).await?;
+        let mut hashes = HashSet::new();
+        let cards: Vec<SystemaDocumentCard> = docs.into_iter().filter(|doc| hashes.insert(doc.hash.clone())).collect();
+        info!("за период {} - {} найдено {} документов", date_from, date_to, cards.len());
+        let failures = shortfalls.into_iter().map(|(day, total, received)| BatchFailure::shortfall(day, total, received)).collect();
+        Ok((cards, failures))
+    }
+
+    ///Пакетная загрузка всех документов подписанных в указанный период
+    /// документы скачиваются параллельно (не более `concurrency` одновременно), каждый готовый `DocumentNodes` передается в `on_document`
+    /// ошибка получения или обработки отдельного документа не прерывает загрузку а попадает в `BatchReport::failures`
+    pub async fn process_period<CONV, CONT, F>(&self, date_from: Date, date_to: Date, kinds: &[DocumentKind], concurrency: usize, converter: &CONV, mut on_document: F) -> Result<BatchReport>
+    where   CONT: ToString + Debug,
+            CONV: Converter<CONT>,
+            F: FnMut(DocumentNodes<CONT>) -> Result<()>
+    {
+        let (cards, failures) = self.search_period_checked(date_from, date_to, kinds).await?;
+        let mut report = BatchReport
+        {
+            total: cards.len(),
+            processed: Vec::with_capacity(cards.len()),
+            failures
+        };
+        let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+        let mut tasks = JoinSet::new();
+        let mut cards_by_task = HashMap::with_capacity(cards.len());
+        for card in cards
+        {
+            let semaphore = semaphore.clone();
+            let client = self.client();
+            let task_card = card.clone();
+            let handle = tasks.spawn(async move
+            {
+                let _permit = semaphore.acquire_owned().await;
+                client.get_document_by_card(task_card).await
+            });
+            cards_by_task.insert(handle.id(), card);
+        }
+        while let Some(joined) = tasks.join_next_with_id().await
+        {
+            let (id, result) = match joined
+            {
+                Ok((id, result)) => (id, result),
+                Err(e) => (e.id(), Err(Error::ApiError(e.to_string())))
+            };
+            let card = &cards_by_task[&id];
+            let result = result
+                .and_then(|response| builder::build_nodes(response, converter))
+                .and_then(|nodes| on_document(nodes));
+            match result
+            {
+                Ok(_) =>
+                {
+                    info!("документ {} от {} обработан ({}/{})", card.number, card.sign_date, report.processed.len() + 1, report.total);
+                    report.processed.push(card.hash.clone());
+                }
+                Err(e) =>
+                {
+                    error!("ошибка обработки документа {} от {}: {}", card.number, card.sign_date, e);
+                    report.failures.push(BatchFailure::new(card, &e));
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+///Поиск за период: помесячно, месяц за который `search` вернул меньше документов чем нашел - по дням  
+/// `search(from, to)` возвращает документы и сколько всего их найдено, кроме документов возвращаются дни
+/// за которые и по одному дню получено не все: день, найдено, получено
+async fn search_ranges<T, F, Fut>(date_from: &Date, date_to: &Date, mut search: F) -> Result<(Vec<T>, Vec<(Date, u32, usize)>)>
+where   F: FnMut(Date, Date) -> Fut,
+        Fut: Future<Output = Result<(Vec<T>, u32)>>
+{
+    let mut found = Vec::new();
+    let mut shortfalls = Vec::new();
+    for (from, to) in month_ranges(date_from, date_to)
+    {
+        let (docs, total) = search(from.clone(), to.clone()).await?;
+        if (docs.len() as u32) < total
+        {
+            info!("за период {} - {} найдено {} документов, получено {}, запрашиваем по дням", from, to, total, docs.len());
+            for day in day_range(&from, &to)
+            {
+                let (docs, total) = search(day.clone(), day.clone()).await?;
+                if (docs.len() as u32) < total
+                {
+                    warn!("за {} найдено {} документов, но получено только {}", day, total, docs.len());
+                    shortfalls.push((day, total, docs.len()));
+                }
+                found.extend(docs);
+            }
+        }
+        else
+        {
+            found.extend(docs);
+        }
+    }
+    Ok((found, shortfalls))
+}
+
+///день, месяц, год
+fn split_date(date: &Date) -> (u32, u32, u32)
+{
+    (date.day(), date.month(), date.year() as u32)
+}
+fn days_in_month(month: u32, year: u32) -> u32
+{
+    match month
+    {
+        2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31
+    }
+}
+///Разбивка периода на месяцы, крайние месяцы обрезаются по границам периода
+fn month_ranges(from: &Date, to: &Date) -> Vec<(Date, Date)>
+{
+    let (from_day, mut month, mut year) = split_date(from);
+    let (to_day, to_month, to_year) = split_date(to);
+    let mut ranges = Vec::new();
+    let mut start_day = from_day;
+    while (year, month) <= (to_year, to_month)
+    {
+        let end_day = if (year, month) == (to_year, to_month) { to_day } else { days_in_month(month, year) };
+        ranges.push((Date::new_date(start_day, month, year), Date::new_date(end_day, month, year)));
+        start_day = 1;
+        month += 1;
+        if month > 12
+        {
+            month = 1;
+            year += 1;
+        }
+    }
+    ranges
+}
+///Все дни периода внутри одного месяца
+fn day_range(from: &Date, to: &Date) -> Vec<Date>
+{
+    let (from_day, month, year) = split_date(from);
+    let (to_day, _, _) = split_date(to);
+    (from_day..=to_day).map(|d| Date::new_date(d, month, year)).collect()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use tracing::info;
+    use utilites::Date;
+    use crate::{SystemaClient, converter, logger, search_attributes::DocumentKind};
+
+    struct NotConvert;
+    impl converter::Converter<String> for NotConvert
+    {
+        fn convert(&self, html: String) -> String
+        {
+            html
+        }
+    }
+
+    #[test]
+    fn test_month_ranges()
+    {
+        let ranges = super::month_ranges(&Date::new_date(15, 11, 2023), &Date::new_date(10, 02, 2024));
+        let ranges: Vec<(String, String)> = ranges.into_iter().map(|(f, t)| (f.format(utilites::DateFormat::DotDate), t.format(utilites::DateFormat::DotDate))).collect();
+        assert_eq!(ranges, vec![
+            ("15.11.2023".to_owned(), "30.11.2023".to_owned()),
+            ("01.12.2023".to_owned(), "31.12.2023".to_owned()),
+            ("01.01.2024".to_owned(), "31.01.2024".to_owned()),
+            ("01.02.2024".to_owned(), "10.02.2024".to_owned()),
+        ]);
+        assert_eq!(super::days_in_month(2, 2024), 29);
+        assert_eq!(super::days_in_month(2, 1900), 28);
+    }
+
+    #[tokio::test]
+    async fn test_search_ranges()
+    {
+        use utilites::DateFormat;
+        //в ноябре документов больше чем отдается за запрос, 20 ноября больше даже за один день
+        let mut requests = Vec::new();
+        let (found, shortfalls) = super::search_ranges(&Date::new_date(18, 11, 2023), &Date::new_date(5, 12, 2023), |from: Date, to: Date|
+        {
+            let range = (from.format(DateFormat::DotDate), to.format(DateFormat::DotDate));
+            requests.push(range.clone());
+            let result = match (range.0.as_str(), range.1.as_str())
+            {
+                ("18.11.2023", "30.11.2023") => (vec![1, 2], 4),
+                ("19.11.2023", _) => (vec![1], 1),
+                ("20.11.2023", _) => (vec![2, 3], 3),
+                ("01.12.2023", "05.12.2023") => (vec![5], 1),
+                _ => (Vec::new(), 0)
+            };
+            async move { Ok(result) }
+        }).await.unwrap();
+        assert_eq!(requests.len(), 1 + 13 + 1);
+        assert_eq!(requests[1], ("18.11.2023".to_owned(), "18.11.2023".to_owned()));
+        assert_eq!(requests[13], ("30.11.2023".to_owned(), "30.11.2023".to_owned()));
+        assert_eq!(found, vec![1, 2, 3, 5]);
+        assert_eq!(shortfalls.len(), 1);
+        assert_eq!(shortfalls[0].0.format(DateFormat::DotDate), "20.11.2023");
+        assert_eq!((shortfalls[0].1, shortfalls[0].2), (3, 2));
+    }
+
+    #[tokio::test]
+    async fn test_process_period()
+    {
+        logger::init();
+        let report = SystemaClient::default().process_period(Date::new_date(1, 7, 2025), Date::new_date(31, 7, 2025), &[DocumentKind::Fz, DocumentKind::Fkz], 4, &NotConvert, |doc|
+        {
+            info!("получен документ {} узлов: {}", doc.number(), doc.node_count());
+            Ok(())
+        }).await.unwrap();
+        info!("обработано {} из {}, ошибок: {}", report.processed.len(), report.total, report.failures.len());
+        assert_eq!(report.processed.len() + report.failures.len(), report.total);
+    }
+}
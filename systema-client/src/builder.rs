@@ -0,0 +1,195 @@
+use std::{collections::BTreeMap, fmt::Debug, sync::LazyLock};
+use regex::Regex;
+use tracing::info;
+use crate::{actual_redactions_client::DocumentResponse, annotations::{self, Amendment}, appendix, effective_date::EffectiveDate, metadata::{self, MetadataParser}, converter::Converter, document::{DocumentNode, DocumentNodes}, error::Result, models::ContentItem, stream};
+
+const HEADER_CLASSES: [&str; 5] = ["I", "C", "T", "Z", "Y"];
+///"2. Текст", "2) текст", "Примечания. 1. Текст"
+static NUMBERED_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*(Примечани[еяй]\s*[.:]?\s*)?(?<number>\d+)[.)](\s|$)").unwrap());
+
+///Блок примечаний к узлу: начинается заголовком "Примечание." и идет пока не сменится класс параграфа
+/// или не начнется нумерованный параграф (следующая часть или пункт статьи)
+/// пункты "Примечаний." нумеруются сами (1., 2., ...), такой блок обрывает только номер не по порядку
+#[derive(Default)]
+struct Notes
+{
+    active: bool,
+    ///класс параграфов блока, по первому параграфу после заголовка
+    class: Option<String>,
+    ///номер следующего пункта примечаний, если они нумеруются
+    next_number: Option<usize>
+}
+impl Notes
+{
+    fn start(&mut self, heading: &str)
+    {
+        let heading = heading.replace('\u{a0}', " ");
+        let plural = heading.trim_start().starts_with("Примечания");
+        self.active = true;
+        self.class = None;
+        self.next_number = match number(&heading)
+        {
+            Some(n) => Some(n + 1),
+            None if plural => Some(1),
+            None => None
+        };
+    }
+    fn stop(&mut self)
+    {
+        self.active = false;
+    }
+    ///Параграф продолжает блок примечаний, если нет - блок закончился
+    fn continues(&mut self, text: &str, class: &str) -> bool
+    {
+        if !self.active
+        {
+            return false;
+        }
+        let class_changed = self.class.as_ref().is_some_and(|c| c != class);
+        let numbered = number(&text.replace('\u{a0}', " "));
+        if class_changed || numbered.is_some_and(|n| self.next_number != Some(n))
+        {
+            self.stop();
+            return false;
+        }
+        if let Some(n) = numbered
+        {
+            self.next_number = Some(n + 1);
+        }
+        self.class.get_or_insert_with(|| class.to_owned());
+        true
+    }
+}
+fn number(text: &str) -> Option<usize>
+{
+    NUMBERED_RX.captures(text).and_then(|c| c.name("number")).and_then(|n| n.as_str().parse().ok())
+}
+
+///Построение дерева узлов документа из полученной редакции
+/// содержание (`getcontent`) задает границы и уровни узлов, параграфы вне содержания добавляются как `параграф`
+/// приложения в содержание не входят, каждое строится как отдельный корень `приложение`
+pub(crate) fn build_nodes<CONV, CONT>(document: DocumentResponse, converter: &CONV) -> Result<DocumentNodes<CONT>>
+where   CONT: ToString + Debug,
+        CONV: Converter<CONT>
+{
+    let contents = document.contents;
+    let mut content_map = BTreeMap::new();
+    let mut document_nodes = DocumentNodes::new(document.name, document.number, document.sign_date, document.publication_url, document.state, document.hash, document.redaction_id);
+    for content in contents.content
+    {
+        let item: ContentItem = content.try_into()?;
+        content_map.insert(item.start, item);
+    }
+    //кодексы это мегабайты html, поэтому параграфы разбираются потоково, без DOM всего документа и без списка всех параграфов
+    //первый проход: реквизиты и тексты параграфов для поиска приложений, их границы нужны до построения узлов
+    let mut metadata_parser = MetadataParser::new(content_map.keys().next().copied());
+    let mut texts: Vec<(usize, String)> = Vec::new();
+    stream::stream_paragraphs(&document.html, |p|
+    {
+        metadata_parser.add(&p);
+        if let Some(id) = p.id
+        {
+            texts.push((id, p.text));
+        }
+    });
+    document_nodes.set_metadata(metadata_parser.finish());
+    let appendices = appendix::find_appendices(&texts, &content_map);
+    drop(texts);
+    let mut current_lvl = 0;
+    //последний узел из содержания и признак того что идет блок примечаний к нему
+    let mut current_item: Option<usize> = None;
+    let mut notes = Notes::default();
+    //второй проход: узлы строятся по одному параграфу
+    stream::stream_paragraphs(&document.html, |p|
+    {
+        if let Some(id) = p.id
+        {
+            let appendix = appendices.iter().find(|a| a.contains(id));
+            //шапка и подпись уже разобраны в реквизиты, узлами такие параграфы становятся только внутри приложений
+            if appendix.is_none() && HEADER_CLASSES.iter().any(|c| p.has_class(c))
+            {
+                return;
+            }
+            let links = if p.links.is_empty() {None} else { info!("Обрнаружены ссылки: {:?}", &p.links); Some(p.links) };
+
+            //аннотации об изменениях в конвертированный текст не попадают
+            let mut amendments = p.amendments;
+            let mut repealed = amendments.iter().any(|a| a.is_repeal());
+            let text = p.text;
+            //строки о принятии и одобрении уже есть в реквизитах
+            if metadata::is_adoption_line(&text)
+            {
+                return;
+            }
+            if !repealed && annotations::is_repealed_text(&text)
+            {
+                amendments.push(Amendment::repeal_from_text(&text));
+                repealed = true;
+            }
+            document_nodes.add_effective_dates(EffectiveDate::parse(&text));
+            let content = converter.convert(p.html_without_amendments);
+            if let Some(content_item) = content_map.get(&id)
+            {
+                current_lvl = content_item.lvl;
+                notes.stop();
+                let node = DocumentNode::new(&content_item.name, p.html, content, links, content_item.start, content_item.end, content_item.lvl, &content_item.caption)
+                    .with_amendments(amendments, repealed);
+                current_item = document_nodes.insert(node);
+            }
+            else if let Some(appendix) = appendix && appendix.start == id
+            {
+                current_lvl = 0;
+                notes.stop();
+                let node = DocumentNode::new("приложение", p.html, content, links, appendix.start, appendix.end, 0, &appendix.caption)
+                    .with_amendments(amendments, repealed);
+                current_item = document_nodes.insert(node);
+            }
+            else if let Some(item_idx) = current_item && (notes.continues(&text, &p.class) || annotations::is_note_heading(&text) || annotations::is_footnote(&text))
+            {
+                //примечания и сноски не отдельные узлы, а часть текста узла к которому относятся
+                if annotations::is_note_heading(&text)
+                {
+                    notes.start(&text);
+                }
+                document_nodes.add_note(item_idx, content.to_string());
+            }
+            else
+            {
+                //надо проверять что он находиться в каком-то из диапазонов и только тогда добавлять а иначе вообще не добавлять
+                let node = DocumentNode::new("параграф", p.html, content, links, id, id, current_lvl + 1, "параграф")
+                    .with_amendments(amendments, repealed);
+                document_nodes.insert(node);
+            }
+        }
+    });
+    document_nodes.add_appendices(appendices);
+    Ok(document_nodes)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::Notes;
+
+    #[test]
+    fn test_notes()
+    {
+        let mut notes = Notes::default();
+        assert!(!notes.continues("Текст", "M"));
+        //примечание к части 1, следующая часть статьи уже не примечание
+        notes.start("Примечание.");
+        assert!(notes.continues("Под организацией понимается юридическое лицо.", "M"));
+        assert!(notes.continues("Положения применяются также к филиалам.", "M"));
+        assert!(!notes.continues("2. Налогоплательщики обязаны уплачивать налоги.", "M"));
+        assert!(!notes.continues("Продолжение части 2.", "M"));
+        //у "Примечаний" свои номера, блок обрывает номер не по порядку
+        notes.start("Примечания. 1. Под организацией понимается юридическое лицо.");
+        assert!(notes.continues("2) Под филиалом понимается обособленное подразделение.", "M"));
+        assert!(notes.continues("3. Положения применяются также к представительствам.", "M"));
+        assert!(!notes.continues("2. Налогоплательщики обязаны уплачивать налоги.", "M"));
+        //смена класса параграфа тоже конец блока
+        notes.start("Примечание:");
+        assert!(notes.continues("Под организацией понимается юридическое лицо.", "M"));
+        assert!(!notes.continues("Статья 4. Обязанности", "H"));
+    }
+}
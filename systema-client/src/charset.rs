@@ -0,0 +1,93 @@
+use std::sync::LazyLock;
+use ::encoding::{DecoderTrap, Encoding, EncodingRef, label::encoding_from_whatwg_label};
+use regex::Regex;
+use tracing::{info, warn};
+
+static CHARSET_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"(?i)charset\s*=\s*["']?(?<charset>[\w\-]+)"#).unwrap());
+///в каком объеме начала документа ищем `<meta charset>`
+const META_LOOKUP: usize = 2048;
+const FALLBACK_CHARSET: &str = "windows-1251";
+
+///Результат декодирования ответа
+#[derive(Debug)]
+pub struct Decoded
+{
+    pub text: String,
+    ///кодировка которой декодировали
+    pub charset: String,
+    ///сколько символов не удалось декодировать (заменены на U+FFFD)
+    pub replacements: usize
+}
+
+///Кодировка из заголовка `Content-Type`: `text/html; charset=windows-1251`
+fn charset_from_content_type(content_type: &str) -> Option<String>
+{
+    CHARSET_RX.captures(content_type).and_then(|c| c.name("charset")).map(|c| c.as_str().to_lowercase())
+}
+
+///Кодировка из `<meta charset="...">` или `<meta http-equiv="Content-Type" content="text/html; charset=...">`
+fn charset_from_meta(bytes: &[u8]) -> Option<String>
+{
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(META_LOOKUP)]);
+    let lower = head.to_lowercase();
+    let meta = lower.find("<meta")?;
+    lower[meta..].split("<meta").skip(1).find_map(|tag|
+    {
+        let tag = tag.split('>').next().unwrap_or_default();
+        charset_from_content_type(tag)
+    })
+}
+
+///Декодирование html ответа: кодировка берется из `Content-Type`, затем из `<meta>`,
+/// если нигде не указана то utf-8 когда байты корректны, иначе windows-1251
+/// некорректные байты не прерывают декодирование, а заменяются на U+FFFD
+pub fn decode_html(bytes: &[u8], content_type: Option<&str>) -> Decoded
+{
+    let charset = content_type.and_then(charset_from_content_type)
+        .or_else(|| charset_from_meta(bytes))
+        .unwrap_or_else(|| if std::str::from_utf8(bytes).is_ok() { "utf-8".to_owned() } else { FALLBACK_CHARSET.to_owned() });
+    let encoding: EncodingRef = encoding_from_whatwg_label(&charset).unwrap_or_else(||
+    {
+        warn!("неизвестная кодировка `{}`, декодируем как {}", charset, FALLBACK_CHARSET);
+        encoding_from_whatwg_label(FALLBACK_CHARSET).unwrap()
+    });
+    let text = encoding.decode(bytes, DecoderTrap::Replace).unwrap_or_default();
+    let replacements = text.chars().filter(|c| *c == '\u{FFFD}').count();
+    if replacements > 0
+    {
+        warn!("при декодировании ответа в {} заменено {} некорректных символов", charset, replacements);
+    }
+    else
+    {
+        info!("ответ декодирован в {}", charset);
+    }
+    Decoded { text, charset, replacements }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use ::encoding::{EncoderTrap, Encoding, all::WINDOWS_1251};
+
+    #[test]
+    fn test_decode_html()
+    {
+        let mut bytes = WINDOWS_1251.encode("<html><head><meta http-equiv=\"Content-Type\" content=\"text/html; charset=windows-1251\"></head><body>Федеральный закон</body></html>", EncoderTrap::Strict).unwrap();
+        let decoded = super::decode_html(&bytes, None);
+        assert_eq!(decoded.charset, "windows-1251");
+        assert!(decoded.text.contains("Федеральный закон"));
+        assert_eq!(decoded.replacements, 0);
+        //0x98 в windows-1251 не определен
+        bytes.insert(0, 0x98);
+        let decoded = super::decode_html(&bytes, Some("text/html; charset=windows-1251"));
+        assert!(decoded.text.contains("Федеральный закон"));
+        assert_eq!(decoded.replacements, 1);
+
+        let decoded = super::decode_html("<p>Статья 1</p>".as_bytes(), None);
+        assert_eq!(decoded.charset, "utf-8");
+        assert_eq!(decoded.text, "<p>Статья 1</p>");
+        let decoded = super::decode_html(&WINDOWS_1251.encode("<p>Статья 1</p>", EncoderTrap::Strict).unwrap(), None);
+        assert_eq!(decoded.charset, "windows-1251");
+        assert_eq!(decoded.text, "<p>Статья 1</p>");
+    }
+}
@@ -0,0 +1,157 @@
+use hyper::header::{ACCEPT, ACCEPT_ENCODING, ACCEPT_LANGUAGE, HOST, HeaderName, REFERER, UPGRADE_INSECURE_REQUESTS, USER_AGENT};
+use tracing::warn;
+use crate::error::{Error, Result};
+
+const EBPI_URL: &str = "http://actual.pravo.gov.ru:8000/api/ebpi";
+const IPS_URL: &str = "http://pravo.gov.ru/proxy/ips/";
+const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (X11; Linux x86_64; rv:127.0) Gecko/20100101 Firefox/127.0";
+const REFERER_URL: &str = "http://pravo.gov.ru/";
+
+///Настройки клиентов api (`ActualRedactionsClient`, `SystemaIpsApi`)
+/// по умолчанию ходим напрямую на pravo.gov.ru, для работы через прокси или с локальным mock сервером адреса меняются здесь
+/// клиент без tls: адреса api и прокси только `http://`, с `https://` запрос сразу возвращает ошибку
+#[derive(Debug, Clone)]
+pub struct ClientConfig
+{
+    ///api актуальных редакций `http://actual.pravo.gov.ru:8000/api/ebpi`
+    pub ebpi_url: String,
+    ///ИПС `http://pravo.gov.ru/proxy/ips/`
+    pub ips_url: String,
+    ///`http://proxy.local:3128`
+    pub proxy: Option<String>,
+    pub user_agent: String,
+    ///пауза между повторами запроса в мс, растет от `timeout_from` до `timeout_to`
+    pub timeout_from: u64,
+    pub timeout_to: u64,
+    ///таймаут установки соединения в мс
+    pub connect_timeout: u64,
+    ///таймаут запроса в мс, от отправки до получения всего тела ответа
+    pub request_timeout: u64,
+    ///количество попыток запроса
+    pub retries: u8,
+    ///максимальный размер ответа в байтах, ответ больше считается ошибкой
+    pub max_body_size: usize
+}
+
+impl Default for ClientConfig
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            ebpi_url: EBPI_URL.to_owned(),
+            ips_url: IPS_URL.to_owned(),
+            proxy: None,
+            user_agent: DEFAULT_USER_AGENT.to_owned(),
+            timeout_from: 150,
+            timeout_to: 950,
+            connect_timeout: 10_000,
+            request_timeout: 120_000,
+            retries: 10,
+            max_body_size: 50 * 1024 * 1024
+        }
+    }
+}
+
+impl ClientConfig
+{
+    ///Настройки по умолчанию, переопределенные переменными окружения
+    /// `SYSTEMA_EBPI_URL`, `SYSTEMA_IPS_URL`, `SYSTEMA_PROXY` (или `HTTP_PROXY`), `SYSTEMA_USER_AGENT`,
+    /// `SYSTEMA_TIMEOUT_FROM`, `SYSTEMA_TIMEOUT_TO`, `SYSTEMA_CONNECT_TIMEOUT`, `SYSTEMA_REQUEST_TIMEOUT` (мс),
+    /// `SYSTEMA_RETRIES`, `SYSTEMA_MAX_BODY_SIZE` (байт)
+    pub fn from_env() -> Self
+    {
+        Self::from_vars(|key| std::env::var(key).ok())
+    }
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self
+    {
+        let env = |key: &str| var(key).filter(|v| !v.is_empty());
+        //некорректное число не роняет клиента, остается значение по умолчанию
+        fn number<T: std::str::FromStr>(key: &str, value: Option<String>, default: T) -> T
+        {
+            match value.map(|v| v.trim().parse::<T>())
+            {
+                Some(Ok(value)) => value,
+                Some(Err(_)) =>
+                {
+                    warn!("некорректное значение {}, используется значение по умолчанию", key);
+                    default
+                }
+                None => default
+            }
+        }
+        let default = Self::default();
+        Self
+        {
+            ebpi_url: env("SYSTEMA_EBPI_URL").unwrap_or(default.ebpi_url),
+            ips_url: env("SYSTEMA_IPS_URL").unwrap_or(default.ips_url),
+            proxy: env("SYSTEMA_PROXY").or(env("HTTP_PROXY")).or(env("http_proxy")),
+            user_agent: env("SYSTEMA_USER_AGENT").unwrap_or(default.user_agent),
+            timeout_from: number("SYSTEMA_TIMEOUT_FROM", env("SYSTEMA_TIMEOUT_FROM"), default.timeout_from),
+            timeout_to: number("SYSTEMA_TIMEOUT_TO", env("SYSTEMA_TIMEOUT_TO"), default.timeout_to),
+            connect_timeout: number("SYSTEMA_CONNECT_TIMEOUT", env("SYSTEMA_CONNECT_TIMEOUT"), default.connect_timeout),
+            request_timeout: number("SYSTEMA_REQUEST_TIMEOUT", env("SYSTEMA_REQUEST_TIMEOUT"), default.request_timeout),
+            retries: number("SYSTEMA_RETRIES", env("SYSTEMA_RETRIES"), default.retries),
+            max_body_size: number("SYSTEMA_MAX_BODY_SIZE", env("SYSTEMA_MAX_BODY_SIZE"), default.max_body_size)
+        }
+    }
+    pub fn with_ebpi_url(mut self, url: &str) -> Self
+    {
+        self.ebpi_url = url.to_owned();
+        self
+    }
+    pub fn with_ips_url(mut self, url: &str) -> Self
+    {
+        self.ips_url = url.to_owned();
+        self
+    }
+    pub fn with_proxy(mut self, proxy: &str) -> Self
+    {
+        self.proxy = Some(proxy.to_owned());
+        self
+    }
+    pub(crate) fn headers(&self, host: String) -> Vec<(HeaderName, String)>
+    {
+        let mut h= Vec::new();
+        h.push((HOST, host));
+        h.push((USER_AGENT, self.user_agent.clone()));
+        h.push((ACCEPT, "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,*/*;q=0.8".to_owned()));
+        h.push((ACCEPT_ENCODING, "gzip, deflate".to_owned()));
+        h.push((ACCEPT_LANGUAGE, "ru-RU,ru;q=0.8,en-US;q=0.5,en;q=0.3".to_owned()));
+        h.push((REFERER, REFERER_URL.to_owned()));
+        h.push((UPGRADE_INSECURE_REQUESTS, "1".to_owned()));
+        h
+    }
+    ///Проверка размера ответа
+    pub(crate) fn check_body_size(&self, size: usize) -> Result<()>
+    {
+        if size > self.max_body_size
+        {
+            Err(Error::ApiError(["Размер ответа ", &size.to_string(), " байт превышает допустимый ", &self.max_body_size.to_string()].concat()))
+        }
+        else
+        {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::ClientConfig;
+
+    #[test]
+    fn test_client_config()
+    {
+        let config = ClientConfig::default().with_ebpi_url("http://127.0.0.1:8080/api/ebpi");
+        assert_eq!(crate::http::uri(&config.ebpi_url, "redtext", &[]).unwrap().to_string(), "http://127.0.0.1:8080/api/ebpi/redtext");
+        assert!(config.headers("127.0.0.1:8080".to_owned()).iter().all(|(_, v)| !v.contains(":://")));
+        assert!(config.check_body_size(config.max_body_size + 1).is_err());
+        let vars = [("SYSTEMA_TIMEOUT_FROM", "300"), ("SYSTEMA_TIMEOUT_TO", "5000"), ("SYSTEMA_RETRIES", "много"), ("SYSTEMA_MAX_BODY_SIZE", "1048576"), ("SYSTEMA_REQUEST_TIMEOUT", "30000")];
+        let config = ClientConfig::from_vars(|key| vars.iter().find(|(k, _)| *k == key).map(|(_, v)| v.to_string()));
+        assert_eq!((config.timeout_from, config.timeout_to, config.max_body_size), (300, 5000, 1048576));
+        assert_eq!((config.connect_timeout, config.request_timeout), (ClientConfig::default().connect_timeout, 30000));
+        assert_eq!(config.retries, ClientConfig::default().retries);
+    }
+}
@@ -2,8 +2,15 @@ use std::{collections::{BTreeMap, HashMap}, fmt::Debug};
 use serde::{Deserialize, Serialize};
 use tracing::{error, info, warn};
 use utilites::Date;
-use crate::{Error, actual_redactions_client::DocumentResponse, models::Content};
+use crate::{Error, actual_redactions_client::DocumentResponse, annotations::Amendment, appendix::Appendix, effective_date::{EffectiveDate, EffectiveScope}, temporal::{self, Address}, metadata::DocumentMetadata, models::{Content, DocumentState}};
 const MAX_LVL: usize = 10;
+///Страница документа на портале: `http://actual.pravo.gov.ru/list.html#hash=<dochash>`
+/// параграф в ней открывается через `&paraid=p<id>` (`PARAGRAPH_PARAM`), имя параметра взято по аналогии
+/// со ссылками внутри текста `cmdprm="gohash=<dochash> goparaid=p<id> goback=1"`: `gohash` в адресе страницы это `hash`,
+/// значит `goparaid` - `paraid`. На живом портале переход к параграфу по такой ссылке не проверен,
+/// если портал ждет другое имя меняется только `PARAGRAPH_PARAM`, ссылка на документ целиком от него не зависит
+const DOCUMENT_URL: &str = "http://actual.pravo.gov.ru/list.html#hash=";
+const PARAGRAPH_PARAM: &str = "&paraid=p";
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DocumentNode<C: ToString + Debug>
@@ -18,6 +25,15 @@ pub struct DocumentNode<C: ToString + Debug>
     content_end_id: usize,
     content_lvl: usize,
     caption: String,
+    ///аннотации об изменениях узла (`span.markx`)
+    #[serde(default)]
+    amendments: Vec<Amendment>,
+    ///узел утратил силу
+    #[serde(default)]
+    repealed: bool,
+    ///примечания и сноски к узлу
+    #[serde(default)]
+    notes: Vec<String>,
 }
 impl<C: ToString + Debug> DocumentNode<C> 
 {
@@ -42,8 +58,17 @@ impl<C: ToString + Debug> DocumentNode<C>
             content_end_id,
             content_lvl,
             caption: caption.to_string(),
+            amendments: Vec::new(),
+            repealed: false,
+            notes: Vec::new(),
         }
     }
+    pub fn with_amendments(mut self, amendments: Vec<Amendment>, repealed: bool) -> Self
+    {
+        self.amendments = amendments;
+        self.repealed = repealed;
+        self
+    }
     
     pub fn can_contain(&self, other: &DocumentNode<C>) -> bool 
     {
@@ -75,6 +100,44 @@ impl<C: ToString + Debug> DocumentNode<C>
     {
         self.links.as_ref()
     }
+    pub fn start_id(&self) -> usize
+    {
+        self.content_start_id
+    }
+    pub fn end_id(&self) -> usize
+    {
+        self.content_end_id
+    }
+    pub fn amendments(&self) -> &[Amendment]
+    {
+        &self.amendments
+    }
+    ///Примечания и сноски, относящиеся к узлу
+    pub fn notes(&self) -> &[String]
+    {
+        &self.notes
+    }
+    ///Текст узла вместе с примечаниями, в примечаниях к статьям часто само определение термина
+    pub fn text_with_notes(&self) -> String
+    {
+        let mut text = self.converted_content.to_string();
+        for note in &self.notes
+        {
+            text.push('\n');
+            text.push_str(note);
+        }
+        text
+    }
+    ///Сам узел утратил силу (без учета родителей)
+    pub fn is_repealed(&self) -> bool
+    {
+        self.repealed
+    }
+    ///Аннотация с документом которым узел признан утратившим силу, если она есть
+    pub fn repealed_by(&self) -> Option<&Amendment>
+    {
+        self.amendments.iter().find(|a| a.is_repeal())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -86,6 +149,18 @@ pub struct DocumentNodes<C: ToString + Debug>
     number: String,
     sign_date: Date,
     publication_url: String,
+    ///правовое состояние документа, по умолчанию утратившие силу документы не попадают в поиск
+    #[serde(default)]
+    state: DocumentState,
+    ///положения о вступлении в силу документа и отдельных его статей
+    #[serde(default)]
+    effective_dates: Vec<EffectiveDate>,
+    ///реквизиты из шапки и подписи документа
+    #[serde(default)]
+    metadata: DocumentMetadata,
+    ///приложения, каждое из них отдельный корневой узел
+    #[serde(default)]
+    appendices: Vec<Appendix>,
     nodes: Vec<DocumentNode<C>>,
     //index -> (start, end)
     indexes: BTreeMap<usize, (usize, usize)>,
@@ -117,20 +192,20 @@ impl<C: ToString + Debug> Default for  DocumentNodes<C>
 {
     fn default() -> Self 
     {
-        Self::new("default".to_owned(), "default".to_owned(), Date::now(), "default".to_owned(), "default".to_owned(), 0)
+        Self::new("default".to_owned(), "default".to_owned(), Date::now(), "default".to_owned(), DocumentState::default(), "default".to_owned(), 0)
     }
 }
 impl<C: ToString + Debug> From<DocumentResponse> for DocumentNodes<C>
 {
     fn from(value: DocumentResponse) -> Self 
     {
-        Self::new(value.name, value.number, value.sign_date, value.publication_url, value.hash, value.redaction_id)
+        Self::new(value.name, value.number, value.sign_date, value.publication_url, value.state, value.hash, value.redaction_id)
     }
 }
 
 impl<C: ToString + Debug> DocumentNodes<C> 
 {
-    pub fn new(name: String, number: String, sign_date: Date, publication_url: String, hash: String, redaction_id: u32) -> Self 
+    pub fn new(name: String, number: String, sign_date: Date, publication_url: String, state: DocumentState, hash: String, redaction_id: u32) -> Self 
     {
         Self 
         {
@@ -140,6 +215,10 @@ impl<C: ToString + Debug> DocumentNodes<C>
             number,
             sign_date,
             publication_url,
+            state,
+            effective_dates: Vec::new(),
+            metadata: DocumentMetadata::default(),
+            appendices: Vec::new(),
             nodes: Vec::with_capacity(2000),
             indexes: BTreeMap::new(),
             children: HashMap::with_capacity(2000),
@@ -294,6 +373,11 @@ impl<C: ToString + Debug> DocumentNodes<C>
                 current = Some((parent.content_start_id, parent.content_end_id, parent.content_lvl));
                 result.push(parent);
             }
+            else
+            {
+                //у документов без содержания параграфы висят без родителя
+                break;
+            }
         }
         // Сортируем по уровню (от младшего к старшему)
         result.sort_by_key(|&node| node.content_lvl);
@@ -331,6 +415,22 @@ impl<C: ToString + Debug> DocumentNodes<C>
         
     }
     
+    ///Узел утратил силу сам или утратил силу кто-то из его родителей
+    pub fn is_node_repealed(&self, node: &DocumentNode<C>) -> bool
+    {
+        node.repealed || self.find_all_parents_by_node(node).iter().any(|p| p.repealed)
+    }
+    ///Утратившие силу узлы, из чанков они исключаются но остаются доступны для вопросов по истории документа
+    pub fn repealed_nodes(&self) -> impl Iterator<Item = &DocumentNode<C>>
+    {
+        self.nodes.iter().filter(|n| n.repealed)
+    }
+
+    pub fn get_node(&self, node_idx: usize) -> Option<&DocumentNode<C>>
+    {
+        self.nodes.get(node_idx)
+    }
+
     pub fn get_children(&self, node_idx: usize) -> &[usize] {
         self.children.get(&node_idx).map(|v| v.as_slice()).unwrap_or(&[])
     }
@@ -462,6 +562,77 @@ impl<C: ToString + Debug> DocumentNodes<C>
     {
         &self.name
     }
+    pub fn state(&self) -> DocumentState
+    {
+        self.state
+    }
+    ///Ссылка на документ целиком
+    pub fn document_url(&self) -> String
+    {
+        [DOCUMENT_URL, &self.hash].concat()
+    }
+    ///Ссылка сразу на первый параграф узла, формат см. `DOCUMENT_URL`
+    pub fn node_url(&self, node: &DocumentNode<C>) -> String
+    {
+        [DOCUMENT_URL, &self.hash, PARAGRAPH_PARAM, &node.content_start_id.to_string()].concat()
+    }
+    ///Добавить примечание к узлу с индексом `node_idx` (индекс возвращает `insert`)
+    pub fn add_note(&mut self, node_idx: usize, note: String)
+    {
+        if let Some(node) = self.nodes.get_mut(node_idx)
+        {
+            node.notes.push(note);
+        }
+    }
+    pub fn add_appendices(&mut self, appendices: Vec<Appendix>)
+    {
+        self.appendices.extend(appendices);
+    }
+    pub fn appendices(&self) -> &[Appendix]
+    {
+        &self.appendices
+    }
+    ///Приложение в которое входит узел
+    pub fn appendix_of(&self, node: &DocumentNode<C>) -> Option<&Appendix>
+    {
+        self.appendices.iter().find(|a| a.contains(node.content_start_id))
+    }
+    pub fn set_metadata(&mut self, metadata: DocumentMetadata)
+    {
+        self.metadata = metadata;
+    }
+    pub fn metadata(&self) -> &DocumentMetadata
+    {
+        &self.metadata
+    }
+    pub fn add_effective_dates(&mut self, dates: Vec<EffectiveDate>)
+    {
+        self.effective_dates.extend(dates);
+    }
+    pub fn effective_dates(&self) -> &[EffectiveDate]
+    {
+        &self.effective_dates
+    }
+    ///Когда вступает в силу узел: положение, адрес которого (статья, часть, пункт) содержит узел, самое точное из них,
+    /// если такого нет то для всего документа; положения не действуют для узлов названных в их исключениях
+    pub fn effective_date_for(&self, node: &DocumentNode<C>) -> Option<&EffectiveDate>
+    {
+        let covers = |address: &Address| temporal::find_provision(self, address)
+            .is_some_and(|provision| provision.iter().any(|n| n.start_id() == node.start_id() && n.end_id() == node.end_id()));
+        //уровень адреса: 0 - весь документ, чем больше единиц в адресе тем точнее
+        let depth = |date: &EffectiveDate| match &date.scope
+        {
+            EffectiveScope::Document => Some(0),
+            EffectiveScope::Nodes(addresses) => addresses.iter().filter(|a| covers(a)).map(|a| a.units.len()).max()
+        };
+        self.effective_dates.iter()
+            .filter(|d| !d.exceptions.iter().any(|a| covers(a)))
+            .filter_map(|d| depth(d).map(|depth| (depth, d)))
+            //при равной точности первое по тексту положение
+            .rev()
+            .max_by_key(|(depth, _)| *depth)
+            .map(|(_, d)| d)
+    }
 }
 
 
@@ -530,7 +701,43 @@ mod tests
 
     use rand::Rng;
 
-    use crate::{document::{DocumentNode, DocumentNodes}, logger};
+    use utilites::DateFormat;
+    use crate::{document::{DocumentNode, DocumentNodes}, effective_date::{EffectiveDate, EffectiveScope}, logger};
+
+    #[test]
+    fn test_node_url()
+    {
+        let nodes: DocumentNodes<String> = DocumentNodes::default();
+        let node = DocumentNode::new("статья", "Статья 3".to_string(), "Статья 3".to_string(), None, 51, 60, 0, "Статья 3");
+        assert_eq!(nodes.node_url(&node), "http://actual.pravo.gov.ru/list.html#hash=default&paraid=p51");
+        assert_eq!(nodes.document_url(), "http://actual.pravo.gov.ru/list.html#hash=default");
+    }
+
+    #[test]
+    fn test_effective_date_for()
+    {
+        let mut nodes: DocumentNodes<String> = DocumentNodes::default();
+        let article = |caption: &str, start: usize| DocumentNode::new("статья", caption.to_owned(), caption.to_owned(), None, start, start + 9, 0, caption);
+        let part = |text: &str, id: usize| DocumentNode::new("параграф", text.to_owned(), text.to_owned(), None, id, id, 1, "параграф");
+        for (caption, start) in [("Статья 3", 0), ("Статья 4", 10), ("Статья 12.1", 20), ("Статья 5", 30)]
+        {
+            nodes.insert(article(caption, start));
+        }
+        for (text, id) in [("1. Первая часть.", 31), ("2. Вторая часть.", 32), ("3. Третья часть.", 33)]
+        {
+            nodes.insert(part(text, id));
+        }
+        nodes.add_effective_dates(EffectiveDate::parse("1. Настоящий Федеральный закон вступает в силу со дня его официального опубликования, за исключением статьи 3 и части 3 статьи 5 настоящего Федерального закона."));
+        nodes.add_effective_dates(EffectiveDate::parse("2. Статья 12.1 настоящего Федерального закона вступает в силу с 1 марта 2026 года."));
+        nodes.add_effective_dates(EffectiveDate::parse("3. Часть 2 статьи 5 настоящего Федерального закона вступает в силу с 1 января 2027 года."));
+        assert!(nodes.effective_date_for(&article("Статья 3", 0)).is_none());
+        assert_eq!(nodes.effective_date_for(&article("Статья 4", 10)).map(|d| d.scope.clone()), Some(EffectiveScope::Document));
+        assert!(nodes.effective_date_for(&article("Статья 12.1", 20)).and_then(|d| d.date()).is_some());
+        //части статьи: своя дата, дата документа и исключение из нее
+        assert_eq!(nodes.effective_date_for(&part("2. Вторая часть.", 32)).and_then(|d| d.date()).map(|d| d.format(DateFormat::DotDate)).as_deref(), Some("01.01.2027"));
+        assert_eq!(nodes.effective_date_for(&part("1. Первая часть.", 31)).map(|d| d.scope.clone()), Some(EffectiveScope::Document));
+        assert!(nodes.effective_date_for(&part("3. Третья часть.", 33)).is_none());
+    }
 
     // Тест 1: Базовый функционал
     #[test]
@@ -0,0 +1,148 @@
+use std::sync::LazyLock;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use utilites::Date;
+use crate::temporal::Address;
+
+static ENTRY_INTO_FORCE_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)(?<subject>настоящ\w+\s+федеральн\w+\s+(конституционн\w+\s+)?закон|(стать[яи]|пункт\w*|подпункт\w*|част[ьи]|абзац\w*|глав[аы]|положени[яе])\s+(?:\d+(?:\.\d+)*|[^.;])*?)\s+вступа(ет|ют)\s+в\s+силу\s+(?<rest>[^;]+?)(?:[.;](\s|$)|$)").unwrap());
+static TEXT_DATE_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?<day>\d{1,2})\s+(?<month>января|февраля|марта|апреля|мая|июня|июля|августа|сентября|октября|ноября|декабря)\s+(?<year>\d{4})\s+года").unwrap());
+const MONTHS: [&str; 12] = ["января", "февраля", "марта", "апреля", "мая", "июня", "июля", "августа", "сентября", "октября", "ноября", "декабря"];
+
+///На что распространяется положение о вступлении в силу
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum EffectiveScope
+{
+    ///"Настоящий Федеральный закон вступает в силу ..."
+    Document,
+    ///"Статья 3 настоящего Федерального закона вступает в силу ...", "Части 2 и 3 статьи 5 ..."
+    Nodes(Vec<Address>)
+}
+
+///С какого момента вступает в силу
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum EffectiveStart
+{
+    ///"с 1 сентября 2025 года"
+    Date(Date),
+    ///"со дня его официального опубликования", "по истечении десяти дней после дня его официального опубликования"
+    Condition(String)
+}
+
+///Положение о вступлении в силу из заключительных статей документа
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EffectiveDate
+{
+    pub scope: EffectiveScope,
+    pub start: EffectiveStart,
+    ///"за исключением статьи 3 настоящего Федерального закона" -> `статья 3`
+    pub exceptions: Vec<Address>,
+    ///исходный текст положения
+    pub text: String
+}
+impl EffectiveDate
+{
+    ///Все положения о вступлении в силу найденные в тексте параграфа
+    pub fn parse(text: &str) -> Vec<Self>
+    {
+        let text = text.replace('\u{a0}', " ");
+        ENTRY_INTO_FORCE_RX.captures_iter(&text).filter_map(|cpt|
+        {
+            let subject = cpt.name("subject")?.as_str().trim();
+            let rest = cpt.name("rest")?.as_str().trim();
+            let scope = if subject.to_lowercase().starts_with("настоящ")
+            {
+                EffectiveScope::Document
+            }
+            else
+            {
+                EffectiveScope::Nodes(Address::parse_list(subject))
+            };
+            let (start, exceptions) = match rest.split_once(", за исключением ")
+            {
+                Some((start, exceptions)) => (start, Address::parse_list(exceptions)),
+                None => (rest, Vec::new())
+            };
+            let start = match TEXT_DATE_RX.find(start)
+            {
+                Some(date) if start.starts_with("с ") => parse_text_date(date.as_str()).map(EffectiveStart::Date),
+                _ => None
+            }.unwrap_or(EffectiveStart::Condition(start.to_owned()));
+            Some(Self
+            {
+                scope,
+                start,
+                exceptions,
+                text: cpt.get(0)?.as_str().trim().to_owned()
+            })
+        }).collect()
+    }
+    ///Дата вступления в силу, если она указана явно
+    pub fn date(&self) -> Option<&Date>
+    {
+        match &self.start
+        {
+            EffectiveStart::Date(date) => Some(date),
+            EffectiveStart::Condition(_) => None
+        }
+    }
+}
+
+///Дата записанная текстом: `23 июля 2025 года`
+pub(crate) fn parse_text_date(text: &str) -> Option<Date>
+{
+    let cpt = TEXT_DATE_RX.captures(text)?;
+    let day: u32 = cpt.name("day")?.as_str().parse().ok()?;
+    let month = MONTHS.iter().position(|m| *m == cpt.name("month")?.as_str())? + 1;
+    let year = cpt.name("year")?.as_str();
+    Date::parse(&format!("{:02}.{:02}.{}", day, month, year))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use utilites::DateFormat;
+    use crate::temporal::Address;
+    use super::{EffectiveDate, EffectiveScope, EffectiveStart};
+
+    fn addresses(addresses: &[&str]) -> Vec<Address>
+    {
+        addresses.iter().map(|a| Address::parse(a).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_parse_effective_date()
+    {
+        let dates = EffectiveDate::parse("1. Настоящий Федеральный закон вступает в силу с 1 сентября 2025 года, за исключением статьи 3 настоящего Федерального закона.");
+        assert_eq!(dates.len(), 1);
+        assert_eq!(dates[0].scope, EffectiveScope::Document);
+        assert_eq!(dates[0].date().unwrap().format(DateFormat::DotDate), "01.09.2025");
+        assert_eq!(dates[0].exceptions, addresses(&["статья 3"]));
+
+        let dates = EffectiveDate::parse("2. Статья 3 настоящего Федерального закона вступает в силу с 1 марта 2026 года.");
+        assert_eq!(dates[0].scope, EffectiveScope::Nodes(addresses(&["статья 3"])));
+
+        let dates = EffectiveDate::parse("Настоящий Федеральный закон вступает в силу по истечении десяти дней после дня его официального опубликования.");
+        assert!(matches!(&dates[0].start, EffectiveStart::Condition(c) if c == "по истечении десяти дней после дня его официального опубликования"));
+
+        assert!(EffectiveDate::parse("Статья 8. Вступление в силу настоящего Федерального закона").is_empty());
+
+        let dates = EffectiveDate::parse("3. Статья 12.1 настоящего Федерального закона вступает в силу с 1 марта 2026 года.");
+        assert_eq!(dates[0].scope, EffectiveScope::Nodes(addresses(&["статья 12.1"])));
+
+        let dates = EffectiveDate::parse("Настоящий Федеральный закон вступает в силу со дня его официального опубликования, за исключением статей 3, 5 и 12.1 настоящего Федерального закона.");
+        assert_eq!(dates[0].exceptions, addresses(&["статья 3", "статья 5", "статья 12.1"]));
+
+        //части и пункты статей, а не только статьи целиком
+        let dates = EffectiveDate::parse("4. Части 2 и 3 статьи 5, пункт 4 части 1 статьи 7 настоящего Федерального закона вступают в силу с 1 января 2027 года.");
+        assert_eq!(dates[0].scope, EffectiveScope::Nodes(addresses(&["статья 5 часть 2", "статья 5 часть 3", "статья 7 часть 1 пункт 4"])));
+        let dates = EffectiveDate::parse("Настоящий Федеральный закон вступает в силу с 1 сентября 2025 года, за исключением части 4 статьи 2 и главы 3 настоящего Федерального закона.");
+        assert_eq!(dates[0].exceptions, addresses(&["статья 2 часть 4", "глава 3"]));
+    }
+
+    #[test]
+    fn test_text_date()
+    {
+        assert_eq!(super::parse_text_date("Принят Государственной Думой 23 июля 2025 года").unwrap().format(DateFormat::DotDate), "23.07.2025");
+        assert!(super::parse_text_date("23 июля").is_none());
+    }
+}
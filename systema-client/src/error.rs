@@ -20,6 +20,10 @@ pub enum Error
     ScraperError(String),
     #[error(transparent)]
     SerdeJsonError(#[from] serde_json::error::Error),
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error(transparent)]
+    HyperError(#[from] hyper::Error),
 }
 
 impl Error
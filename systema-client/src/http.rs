@@ -0,0 +1,193 @@
+use std::time::Duration;
+use http_body_util::{BodyExt, Empty};
+use hyper::{Request, StatusCode, Uri, body::Bytes, client::conn::http1::SendRequest, header::{ACCEPT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE}};
+use hyper_util::rt::TokioIo;
+use tokio::net::TcpStream;
+use tracing::warn;
+use crate::{config::ClientConfig, encoding::encode, error::{Error, Result}};
+
+///Ответ сервера вместе с `Content-Type`, `HyperClient` отдает только код и тело
+#[derive(Debug)]
+pub(crate) struct Response
+{
+    pub status: StatusCode,
+    ///`text/html; charset=windows-1251`
+    pub content_type: Option<String>,
+    pub body: Bytes
+}
+
+///Адрес `base/path?key=value`, значения параметров кодируются
+pub(crate) fn uri(base: &str, path: &str, params: &[(&str, &str)]) -> Result<Uri>
+{
+    let mut uri = [base.trim_end_matches('/'), "/", path].concat();
+    for (i, (key, value)) in params.iter().enumerate()
+    {
+        uri.push(if i == 0 { '?' } else { '&' });
+        uri.push_str(key);
+        uri.push('=');
+        uri.push_str(&encode(value));
+    }
+    uri.parse().map_err(|_| Error::ApiError(["Некорректный адрес ", &uri].concat()))
+}
+
+///GET запрос с заголовками и прокси из настроек, при ошибке соединения повторяется `retries` раз
+/// пауза между попытками растет от `timeout_from` до `timeout_to`
+/// тело ответа больше `max_body_size` не читается до конца, это ошибка без повторов
+/// соединение ограничено `connect_timeout`, запрос вместе с чтением тела `request_timeout`, по таймауту запрос повторяется
+/// только http, ИПС и api актуальных редакций работают без tls
+pub(crate) async fn get(config: &ClientConfig, uri: &Uri) -> Result<Response>
+{
+    let retries = config.retries.max(1) as u64;
+    let mut attempt = 1;
+    loop
+    {
+        match request(config, uri).await
+        {
+            Ok(response) => return Ok(response),
+            Err(e) if attempt < retries && matches!(e, Error::IoError(_) | Error::HyperError(_)) =>
+            {
+                let delay = config.timeout_from + config.timeout_to.saturating_sub(config.timeout_from) * attempt / retries;
+                warn!("запрос {} не выполнен: {}, попытка {} из {}", uri, e, attempt, retries);
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e)
+        }
+    }
+}
+
+async fn request(config: &ClientConfig, uri: &Uri) -> Result<Response>
+{
+    if uri.scheme_str() != Some("http")
+    {
+        return Err(Error::ApiError(["Поддерживаются только адреса http, ", &uri.to_string()].concat()));
+    }
+    let proxy: Option<Uri> = match &config.proxy
+    {
+        Some(proxy) => Some(proxy.parse().map_err(|_| Error::ApiError(["Некорректный адрес прокси ", proxy].concat()))?),
+        None => None
+    };
+    let target = proxy.as_ref().unwrap_or(uri);
+    let host = target.host().ok_or(Error::ApiError(["В адресе ", &target.to_string(), " нет хоста"].concat()))?;
+    let connect = TcpStream::connect((host, target.port_u16().unwrap_or(80)));
+    let stream = timeout(config.connect_timeout, "соединение с", target, connect).await??;
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream)).await?;
+    tokio::spawn(async move
+    {
+        if let Err(e) = connection.await
+        {
+            warn!("соединение закрыто с ошибкой: {}", e);
+        }
+    });
+    //через прокси в строке запроса полный адрес, напрямую только путь
+    let path = match proxy
+    {
+        Some(_) => uri.to_string(),
+        None => uri.path_and_query().map(|p| p.as_str()).unwrap_or("/").to_owned()
+    };
+    let mut request = Request::get(path);
+    let authority = uri.authority().map(|a| a.to_string()).unwrap_or_default();
+    //сжатый ответ тут не распаковывается
+    for (name, value) in config.headers(authority).into_iter().filter(|(name, _)| name != ACCEPT_ENCODING)
+    {
+        request = request.header(name, value);
+    }
+    let request = request.body(Empty::<Bytes>::new()).map_err(|e| Error::ApiError(e.to_string()))?;
+    timeout(config.request_timeout, "запрос", uri, read_response(config, &mut sender, request)).await?
+}
+
+///Отправка запроса и чтение ответа с проверкой размера тела
+async fn read_response(config: &ClientConfig, sender: &mut SendRequest<Empty<Bytes>>, request: Request<Empty<Bytes>>) -> Result<Response>
+{
+    let response = sender.send_request(request).await?;
+    let status = response.status();
+    let content_type = response.headers().get(CONTENT_TYPE).and_then(|v| v.to_str().ok()).map(|v| v.to_owned());
+    //по Content-Length отказываемся еще до чтения тела
+    if let Some(length) = response.headers().get(CONTENT_LENGTH).and_then(|v| v.to_str().ok()).and_then(|v| v.parse::<usize>().ok())
+    {
+        config.check_body_size(length)?;
+    }
+    //без Content-Length (chunked) читаем пока тело не превысит допустимый размер
+    let mut frames = response.into_body();
+    let mut body = Vec::new();
+    while let Some(frame) = frames.frame().await
+    {
+        if let Ok(data) = frame?.into_data()
+        {
+            config.check_body_size(body.len() + data.len())?;
+            body.extend_from_slice(&data);
+        }
+    }
+    Ok(Response { status, content_type, body: Bytes::from(body) })
+}
+
+///Таймаут как ошибка ввода-вывода `TimedOut`, такие ошибки запрос повторяют
+async fn timeout<T>(ms: u64, what: &str, uri: &Uri, future: impl Future<Output = T>) -> Result<T>
+{
+    tokio::time::timeout(Duration::from_millis(ms), future).await
+        .map_err(|_| Error::IoError(std::io::Error::new(std::io::ErrorKind::TimedOut, [what, " ", &uri.to_string(), ": таймаут ", &ms.to_string(), " мс"].concat())))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::TcpListener};
+    use crate::{config::ClientConfig, error::Error};
+
+    ///отвечает на один запрос заранее заданным ответом
+    async fn serve(response: String) -> String
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move
+        {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 4096];
+            let _ = socket.read(&mut request).await.unwrap();
+            let _ = socket.write_all(response.as_bytes()).await;
+        });
+        ["http://", &addr.to_string()].concat()
+    }
+
+    #[tokio::test]
+    async fn test_body_size()
+    {
+        let mut config = ClientConfig::default();
+        config.max_body_size = 8;
+        config.retries = 1;
+        let base = serve("HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello".to_owned()).await;
+        let response = super::get(&config, &super::uri(&base, "redtext", &[]).unwrap()).await.unwrap();
+        assert_eq!(&response.body[..], b"hello");
+        //заявленный размер больше допустимого, тело не читается
+        let base = serve("HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\nhello".to_owned()).await;
+        assert!(matches!(super::get(&config, &super::uri(&base, "redtext", &[]).unwrap()).await, Err(Error::ApiError(_))));
+        //размер не заявлен, чтение обрывается на превышении
+        let base = serve("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n5\r\nworld\r\n0\r\n\r\n".to_owned()).await;
+        assert!(matches!(super::get(&config, &super::uri(&base, "redtext", &[]).unwrap()).await, Err(Error::ApiError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout()
+    {
+        let mut config = ClientConfig::default();
+        config.request_timeout = 200;
+        config.retries = 1;
+        //соединение принимается, но ответа нет
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = ["http://", &listener.local_addr().unwrap().to_string()].concat();
+        tokio::spawn(async move
+        {
+            let (_socket, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+        });
+        let result = super::get(&config, &super::uri(&base, "redtext", &[]).unwrap()).await;
+        assert!(matches!(result, Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::TimedOut));
+    }
+
+    #[test]
+    fn test_uri()
+    {
+        let uri = super::uri("http://127.0.0.1:8080/api/ebpi/", "redtext", &[("bpa", "ebpi"), ("t", r#"{"hash":"a b"}"#)]).unwrap();
+        assert_eq!(uri.to_string(), "http://127.0.0.1:8080/api/ebpi/redtext?bpa=ebpi&t=%7B%22hash%22%3A%22a%20b%22%7D");
+    }
+}
@@ -1,17 +1,16 @@
-use ::encoding::{DecoderTrap, Encoding, all::WINDOWS_1251};
 pub use crate::error::Error;
-use crate::{error::Result, parser};
+use crate::{charset, config::ClientConfig, error::Result, http::{self, Response}, parser};
 use std::{cell::LazyCell, fmt::Display, sync::LazyLock};
 use regex::Regex;
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
 use tracing::info;
-use utilites::{Date, Url, http::{ACCEPT, ACCEPT_ENCODING, ACCEPT_LANGUAGE, Bytes, HOST, HeaderName, HyperClient, REFERER, StatusCode, UPGRADE_INSECURE_REQUESTS, USER_AGENT, Uri}};
+use hyper::{StatusCode, Uri};
+use utilites::{Date, Url};
 
 //use crate::{encoding::encode, SystemaApiError};
 
 pub static REDACTIONS_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\d{1,}\s+-\s+\w{2}\s+(?<date>\d{2}[.]\d{2}[.]\d{4})\s+(№\s+(?<number>\d{1,}-[ФЗК]+))?\s+([(](?<comment>[^)]+))?").unwrap());
-const BASE: &str = "http://pravo.gov.ru/proxy/ips/";
 pub enum DocumentKindSearchParams
 {
     Fz,
@@ -108,42 +107,35 @@ impl Editions
 }
 pub struct SystemaIpsApi
 {
-    uri: String
+    uri: String,
+    config: ClientConfig
 }
 /// FIXME ТУТ ВСЕ РАБОТАЕТ, НАДО ПОМЕНЯТЬ КЛИЕНТА! ПОКА В ЭТОМ АПИ НЕТ НЕОБХОДИМОСТИ!
 //http://95.173.147.130/proxy/ips/?list_itself=&bpas=cd00000&a3=102000505;102000506&a3type=1&a3value=&a6=&a6type=1&a6value=&a15=&a15type=1&a15value=&a7type=1&a7from=&a7to=&a7date=29.12.2012&a8=273-%F4%E7&a8type=1&a1=&a0=&a16=&a16type=1&a16value=&a17=&a17type=1&a17value=&a4=&a4type=1&a4value=&a23=&a23type=1&a23value=&textpres=&sort=7&x=49&y=9&page=firstlast
 impl SystemaIpsApi
 {
 
-    fn client() -> HyperClient
+    ///Запрос к ИПС, `path` добавляется к адресу ИПС из настроек `?docbody=&nd=102162745`, размер ответа ограничен `max_body_size`
+    async fn get(config: &ClientConfig, path: &str) -> Result<Response>
     {
-        HyperClient::new(BASE.parse().unwrap()).with_headers(Self::headers())
-    }
-    fn headers() -> Vec<(HeaderName, String)>
-    {
-        let mut h= Vec::new();
-        h.push((HOST, "pravo.gov.ru".to_owned()));
-        h.push((USER_AGENT, "Mozilla/5.0 (X11; Linux x86_64; rv:127.0) Gecko/20100101 Firefox/127.0".to_owned()));
-        h.push((ACCEPT, "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,*/*;q=0.8".to_owned()));
-        h.push((ACCEPT_ENCODING, "gzip, deflate".to_owned()));
-        h.push((ACCEPT_LANGUAGE, "ru-RU,ru;q=0.8,en-US;q=0.5,en;q=0.3".to_owned()));
-        h.push((REFERER, "http:://pravo.gov.ru".to_owned()));
-        h.push((UPGRADE_INSECURE_REQUESTS, "1".to_owned()));
-        h
+        let uri = [config.ips_url.as_str(), path].concat();
+        let uri: Uri = uri.parse().map_err(|_| Error::ApiError(["Некорректный адрес ", &uri].concat()))?;
+        info!("request uri: {:?}", &uri);
+        http::get(config, &uri).await
     }
 
     ///Проверка что пришел код 200 на запрос
-    fn code_error_check(response: (StatusCode, Bytes)) -> Result<Bytes>
+    fn code_error_check(response: Response) -> Result<Response>
     {
-        if response.0 != utilites::http::StatusCode::OK
+        if response.status != StatusCode::OK
         {
-            let e = ["Сервер ответил кодом ", response.0.as_str(), " ожидался код 200"].concat();
+            let e = ["Сервер ответил кодом ", response.status.as_str(), " ожидался код 200"].concat();
             tracing::warn!("{}", &e);
             return Err(Error::ApiError(e));
         }
         else 
         {
-            Ok(response.1)
+            Ok(response)
         }
     }
 
@@ -191,20 +183,17 @@ impl SystemaIpsApi
         search_request
     }
 
-    async fn get_document_id(doc_types: &[DocumentKindSearchParams], doc_number: &str, sign_date: Date) -> Result<String>
+    async fn get_document_id(config: &ClientConfig, doc_types: &[DocumentKindSearchParams], doc_number: &str, sign_date: Date) -> Result<String>
     {
-        let client = Self::client()
-            .with_path(Self::search_uri(doc_types, doc_number, sign_date.clone()));
-        info!("request uri: {:?}", client.get_uri());
-        let (code, data) = client.get().await?;
-        match code
+        let response = Self::get(config, &Self::search_uri(doc_types, doc_number, sign_date.clone())).await?;
+        match response.status
         {
             StatusCode::OK => (),
             StatusCode::NO_CONTENT => return Err(Error::ApiError(["Документ ", doc_number, " ", &sign_date.to_string(), " не найден!"].concat())),
-            _ => return Err(Error::ApiError(format!("статус запроса {}", code)))
+            code => return Err(Error::ApiError(format!("статус запроса {}", code)))
 
         }
-        let body = Self::enc_win1251(&data)?;
+        let body = Self::decode(&response)?;
         let page = Html::parse_document(&body);
         let selector = Selector::parse(r#"a[id="link_0"]"#).unwrap();
         if let Some(element) = page.select(&selector).next()
@@ -237,18 +226,17 @@ impl SystemaIpsApi
         }
     }
 
-    async fn get_editions(doc_types: &[DocumentKindSearchParams], doc_number: &str, sign_date: Date) -> Result<Editions>
+    async fn get_editions(config: &ClientConfig, doc_types: &[DocumentKindSearchParams], doc_number: &str, sign_date: Date) -> Result<Editions>
     {
-        let id = Self::get_document_id(doc_types, doc_number, sign_date).await?;
-        Self::get_editions_by_doc_id(&id).await
+        let id = Self::get_document_id(config, doc_types, doc_number, sign_date).await?;
+        Self::get_editions_by_doc_id(config, &id).await
     }
-    async fn get_editions_by_doc_id(doc_id: &str) -> Result<Editions>
+    async fn get_editions_by_doc_id(config: &ClientConfig, doc_id: &str) -> Result<Editions>
     {
         let doc_uri = ["?docbody=&link_id=0&nd=", &doc_id, "&intelsearch=&firstDoc=1"].concat();
-        let client = Self::client().with_path(&doc_uri);
-        let response = client.get().await?;
+        let response = Self::get(config, &doc_uri).await?;
         let document = Self::code_error_check(response)?;
-        let redactions_html = Self::enc_win1251(&document)?;
+        let redactions_html = Self::decode(&document)?;
         let red_page = Html::parse_document(&redactions_html);
         let selector = Selector::parse(r#"select[name="doc_editions"]"#).unwrap();
         if let Some(element) = red_page.select(&selector).next()
@@ -270,10 +258,9 @@ impl SystemaIpsApi
     }
     pub async fn get_document(&self) -> Result<String>
     {
-        let client = Self::client().with_path(&self.uri);
-        let response = client.get().await?;
+        let response = Self::get(&self.config, &self.uri).await?;
         let document = Self::code_error_check(response)?;
-        let doc_html = Self::enc_win1251(&document)?;
+        let doc_html = Self::decode(&document)?;
         Ok(doc_html)
     }
     pub async fn get_document_html(&self) -> Result<Html>
@@ -287,21 +274,21 @@ impl SystemaIpsApi
         &self.uri
     }
 
-    pub async fn search(doc_types: &[DocumentKindSearchParams], doc_number: &str, sign_date: Date) -> Result<Self>
+    ///Документ по идентификатору ИПС `nd=102162745`
+    pub fn by_nd(config: &ClientConfig, nd: &str) -> Self
+    {
+        Self { uri: ["?doc_itself=&nd=", nd, "&page=1", "&fulltext=1"].concat(), config: config.clone() }
+    }
+    pub async fn search(config: &ClientConfig, doc_types: &[DocumentKindSearchParams], doc_number: &str, sign_date: Date) -> Result<Self>
     {
-        let id = Self::get_document_id(doc_types, doc_number, sign_date).await?;
-        Ok(Self { uri: ["?doc_itself=&nd=", &id, "&page=1", "&fulltext=1"].concat()})
+        let id = Self::get_document_id(config, doc_types, doc_number, sign_date).await?;
+        Ok(Self::by_nd(config, &id))
     }
 
-    fn enc_win1251(bytes: &[u8]) -> Result<String>
+    ///Ответы ИПС обычно в windows-1251, но кодировку проверяем по `Content-Type` и `<meta>`, а некорректные байты заменяем а не роняем документ
+    fn decode(response: &Response) -> Result<String>
     {
-        let result = WINDOWS_1251.decode(&bytes, DecoderTrap::Strict);
-        if result.is_err()
-        {
-            return Err(Error::ApiError(format!("Ошибка открытия html ответа в кодировке windows-1251 {}", result.as_ref().err().unwrap())));
-        }
-        let result = result.unwrap();
-        Ok(result)
+        Ok(charset::decode_html(&response.body, response.content_type.as_deref()).text)
     }
 }
 #[cfg(test)]
@@ -309,7 +296,7 @@ mod tests
 {
     use utilites::Date;
 
-    use crate::logger;
+    use crate::{config::ClientConfig, logger};
 
     use super::DocumentKindSearchParams;
     #[test]
@@ -325,11 +312,32 @@ mod tests
         tracing::info!("{s}");
     }
 
+    #[tokio::test]
+    async fn test_decode_by_content_type()
+    {
+        use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::TcpListener};
+        //кодировка только в заголовке, без него ответ декодировался бы как windows-1251
+        let body = "<html><body><p>Статья 1</p></body></html>";
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move
+        {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 4096];
+            let _ = socket.read(&mut request).await.unwrap();
+            let response = ["HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: ", &body.len().to_string(), "\r\n\r\n", body].concat();
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        let config = ClientConfig::default().with_ips_url(&["http://", &addr.to_string(), "/proxy/ips/"].concat());
+        let doc = super::SystemaIpsApi::by_nd(&config, "102162745").get_document().await.unwrap();
+        assert!(doc.contains("Статья 1"));
+    }
+
     #[tokio::test]
     async fn test_search_doc()
     {
         logger::init();
-        let doc = super::SystemaIpsApi::search(&[DocumentKindSearchParams::Fz, DocumentKindSearchParams::Fkz],
+        let doc = super::SystemaIpsApi::search(&ClientConfig::default(), &[DocumentKindSearchParams::Fz, DocumentKindSearchParams::Fkz],
             "273-фз",
             Date::new_date(29, 12, 2012)).await.unwrap().get_document().await;
        
@@ -342,6 +350,7 @@ mod tests
     {
         logger::init();
         let s = super::SystemaIpsApi::get_editions(
+            &ClientConfig::default(),
             &[DocumentKindSearchParams::Fz, DocumentKindSearchParams::Fkz],
             "273-фз",
             Date::new_date(29, 12, 2012)).await.unwrap();
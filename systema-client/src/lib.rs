@@ -10,84 +10,87 @@ mod converter;
 pub use error::Error;
 mod logger;
 mod parser;
-use crate::{actual_redactions_client::{ActualRedactionsClient}, error::Result, models::ContentItem};
-use std::{collections::BTreeMap, fmt::Debug};
-use scraper::{Selector};
-use tracing::{debug, info};
+mod builder;
+mod batch;
+mod annotations;
+mod effective_date;
+mod metadata;
+mod appendix;
+mod source;
+mod charset;
+mod config;
+mod stream;
+mod temporal;
+mod report;
+mod redline;
+mod resolver;
+mod http;
+use crate::{actual_redactions_client::{ActualRedactionsClient}, error::Result};
+use std::fmt::Debug;
 use utilites::Date;
 pub use document::{DocumentNode, DocumentNodes};
 pub use converter::Converter;
+pub use search_attributes::{DocumentKind, TextScope};
+pub use models::{SystemaDocumentCard, Contents, DocumentState, ExtendedRedaction, Redactions, RedactionType, RedactionStatus};
+pub use actual_redactions_client::{ActualRedactionsClient, DocumentResponse, RedactionTtl, SearchHit};
+pub use batch::{BatchReport, BatchFailure};
+pub use annotations::{Amendment, AmendmentKind, AmendingAct};
+pub use effective_date::{EffectiveDate, EffectiveScope, EffectiveStart};
+pub use metadata::DocumentMetadata;
+pub use appendix::Appendix;
+pub use source::{LegalSource, EbpiSource, IpsSource, LocalFileSource, LocalDirectorySource, LocalMetadata};
+pub use ibpi_client::DocumentKindSearchParams;
+pub use charset::{decode_html, Decoded};
+pub use config::ClientConfig;
+pub use stream::{Paragraph, paragraphs, stream_paragraphs};
+pub use temporal::{Address, AddressUnit, AmendmentEvent, ProvisionAt, ProvisionLookup};
+pub use report::{ChangeKind, NodeChange, RedactionReport};
+pub use redline::{render_redline, RedlineMode};
+pub use resolver::{DocumentRef, DocumentResolver};
+pub use ibpi_client::SystemaIpsApi;
 
+#[derive(Default)]
 pub struct SystemaClient
 {
+    config: ClientConfig
 }
 impl SystemaClient
 {
+    pub fn new(config: ClientConfig) -> Self
+    {
+        Self { config }
+    }
+    fn client(&self) -> ActualRedactionsClient
+    {
+        ActualRedactionsClient::new(self.config.clone())
+    }
     ///Date::new_date(29, 05, 2024), "102-ФЗ"
-    pub async fn get_document<CONV, CONT>(sign_date: Date, number: &str, converter: CONV) -> Result<DocumentNodes<CONT>>
+    pub async fn get_document<CONV, CONT>(&self, sign_date: Date, number: &str, converter: CONV) -> Result<DocumentNodes<CONT>>
     where   CONT: ToString + Debug,
             CONV: converter::Converter<CONT>
 
     {
-        let document = ActualRedactionsClient::get_document(sign_date, number).await?;
-        let contents = document.contents;
-        let html = document.html;
-        let mut content_map = BTreeMap::new();
-        let mut document_nodes = DocumentNodes::new(document.name, document.number, document.sign_date, document.publication_url, document.hash, document.redaction_id);
-        for content in contents.content
-        {
-            let item: Result<ContentItem> = content.try_into();
-            if let Ok(item) = item
-            {
-                content_map.insert(item.start, item);
-            }
-            else 
-            {
-                return Err(item.err().unwrap());
-            }
-        }
-        let selector = Selector::parse("p:not(.I):not(.C):not(.T):not(.Z):not(.Y):not(.mark):not(.markx)").unwrap();
-        let links_selector = Selector::parse("span[cmdprm]").unwrap();
-        let par = html.select(&selector);
-        let mut current_lvl = 0;
-        let mut ci = Vec::new();
-        for p in par
-        {
-            if let Some(str_id) = p.attr("id") && let Some(id ) = str_id.strip_prefix("p").and_then(|p| p.parse().ok())
-            {
-                let links: Vec<String> = p.select(&links_selector).into_iter().filter_map(|l|
-                {
-                    //<span class="cmd-hide" cmdprm="gohash=b113c2e08341853ef53a8dad4585b513d96f85e0f3d0d246a25ecf52e40608db goparaid=0 goback=0">Налогового кодекса Российской Федерации</span>
-                    l.attr("cmdprm").and_then(|cmd| 
-                        {
-                            cmd.split_once(" ")
-                                .and_then(|(left, _)| Some(left))
-                                .and_then(|c|  Some(c.replace("gohash=", "")))
-                        })
-                }).collect();
-                let links = if links.is_empty() {None} else { info!("Обрнаружены ссылки: {:?}", &links); Some(links) };
-                
-                let id: usize = id;
-                if let Some(content_item) = content_map.get(&id)
-                {
-                    current_lvl = content_item.lvl;
-                    let content = converter.convert(p.html());
-                    let node = DocumentNode::new(&content_item.name, p.html(), content, links, content_item.start, content_item.end, content_item.lvl, &content_item.caption);
-                    document_nodes.insert(node);
-                    ci.push(content_item);
-                }
-                else 
-                {
-                    //надо проверять что он находиться в каком-то из диапазонов и только тогда добавлять а иначе вообще не добавлять
-                    let content = converter.convert(p.html());
-                    let node = DocumentNode::new("параграф", p.html(), content, links, id, id, current_lvl + 1, "параграф");
-                    document_nodes.insert(node);
-                }
-            }
-        }
-        //tokio::fs::write("contents.json", &serde_json::to_string_pretty(&ci).unwrap()).await;
-        Ok(document_nodes)
-
+        EbpiSource::new(self.config.clone()).get_document(&(sign_date, number.to_owned()), &converter).await
+    }
+    ///Документ по любой ссылке: хеш (в том числе из `cmdprm`), номер опубликования, `nd` ИПС, ссылка на портал
+    pub async fn get_document_by_ref<CONV, CONT>(&self, document: &DocumentRef, converter: CONV) -> Result<DocumentNodes<CONT>>
+    where   CONT: ToString + Debug,
+            CONV: converter::Converter<CONT>
+    {
+        DocumentResolver::new(self.config.clone()).get_document(document, &converter).await
+    }
+    ///Запросы текста положений на дату (`article_at`) с теми же настройками клиента
+    pub fn provision_lookup<CONV, CONT>(&self, converter: CONV) -> ProvisionLookup<CONV, CONT>
+    where   CONT: ToString + Debug,
+            CONV: converter::Converter<CONT>
+    {
+        ProvisionLookup::new(self.config.clone(), converter)
+    }
+    ///Поиск ФЗ и ФКЗ по тексту или названию, результаты в порядке релевантности  
+    /// нужен чтобы найти документы-кандидаты по вопросу пользователя, даже если их еще нет в индексе
+    pub async fn search_text(&self, query: &str, scope: TextScope, limit: u32) -> Result<Vec<SearchHit>>
+    {
+        self.client().search_text(query, scope, None, Date::now(), &[DocumentKind::Fz, DocumentKind::Fkz], limit).await
     }
 }
 #[cfg(test)]
@@ -111,7 +114,7 @@ mod tests
     {
         logger::init();
         let converter = NotConvert;
-        let doc = super::SystemaClient::get_document(Date::new_date(31, 07, 2025), "287-ФЗ", converter).await.unwrap();
+        let doc = super::SystemaClient::default().get_document(Date::new_date(31, 07, 2025), "287-ФЗ", converter).await.unwrap();
         let stats = doc.stats();
         info!("\nСтатистика дерева:");
         info!("Всего узлов: {}", stats.total_nodes);
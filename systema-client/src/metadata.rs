@@ -0,0 +1,180 @@
+use std::sync::LazyLock;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use utilites::Date;
+use crate::{annotations::Amendment, effective_date, stream::{self, Paragraph}};
+
+static ADOPTION_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*(Принят|Одобрен)\w*\s+(?<body>Государственной\s+Думой|Советом\s+Федерации)").unwrap());
+static SIGNATORY_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*(Президент\s+Российской\s+Федерации|Председатель\s+Правительства)").unwrap());
+static NUMBER_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*№\s*(?<number>[\dА-Яа-яA-Za-z\-/]+)\s*$").unwrap());
+
+///Реквизиты документа из шапки и подписи (параграфы `p.I`, `p.C`, `p.T`, `p.Z`, `p.Y`)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DocumentMetadata
+{
+    ///"ФЕДЕРАЛЬНЫЙ ЗАКОН"
+    pub kind: Option<String>,
+    ///"О принятии в Российскую Федерацию ..."
+    pub title: Option<String>,
+    ///"Принят Государственной Думой 23 июля 2025 года"
+    pub duma_date: Option<Date>,
+    ///"Одобрен Советом Федерации 25 июля 2025 года"
+    pub council_date: Option<Date>,
+    ///"Президент Российской Федерации В.Путин"
+    pub signatory: Option<String>,
+    ///"Москва, Кремль"
+    pub place: Option<String>,
+    ///дата подписания из подписи документа
+    pub sign_date: Option<Date>,
+    ///"5-ФКЗ"
+    pub number: Option<String>,
+    ///текст до первой статьи
+    pub preamble: Option<String>,
+    ///"(В редакции федеральных законов от ...)" под заголовком
+    pub amended_by: Vec<Amendment>
+}
+
+impl DocumentMetadata
+{
+    ///Разбор реквизитов из полного html документа
+    pub fn from_html(html: &str) -> Self
+    {
+        let mut parser = MetadataParser::new(None);
+        stream::stream_paragraphs(html, |p| parser.add(&p));
+        parser.finish()
+    }
+    ///место, дата или номер из подписи документа
+    fn set_signature_line(&mut self, text: String)
+    {
+        if let Some(number) = NUMBER_RX.captures(&text).and_then(|c| c.name("number"))
+        {
+            self.number.get_or_insert(number.as_str().to_owned());
+        }
+        else if let Some(date) = effective_date::parse_text_date(&text)
+        {
+            self.sign_date.get_or_insert(date);
+        }
+        else if self.signatory.is_some()
+        {
+            self.place.get_or_insert(text);
+        }
+    }
+    fn set_adoption(&mut self, text: &str)
+    {
+        let date = effective_date::parse_text_date(text);
+        match ADOPTION_RX.captures(text).and_then(|c| c.name("body")).map(|b| b.as_str())
+        {
+            Some(body) if body.starts_with("Государственной") => self.duma_date = date,
+            Some(_) => self.council_date = date,
+            None => ()
+        }
+    }
+}
+
+///Реквизиты по одному параграфу, документ не нужно держать в памяти целиком
+/// `first_content_id` - первый параграф из содержания, все обычные параграфы до него считаются преамбулой
+pub(crate) struct MetadataParser
+{
+    metadata: DocumentMetadata,
+    preamble: Vec<String>,
+    first_content_id: Option<usize>
+}
+impl MetadataParser
+{
+    pub(crate) fn new(first_content_id: Option<usize>) -> Self
+    {
+        Self { metadata: DocumentMetadata::default(), preamble: Vec::new(), first_content_id }
+    }
+    pub(crate) fn add(&mut self, p: &Paragraph)
+    {
+        let metadata = &mut self.metadata;
+        let text = p.text.split_whitespace().collect::<Vec<&str>>().join(" ");
+        let class = p.class.as_str();
+        //у `p.C` с аннотацией весь текст в `span.markx`
+        if text.is_empty() && class != "C"
+        {
+            return;
+        }
+        if is_adoption_line(&text)
+        {
+            metadata.set_adoption(&text);
+            return;
+        }
+        match class
+        {
+            //в старых документах название тоже в `p.T`, вторым после вида документа
+            //берем только первые значения, дальше могут идти шапки приложений
+            "T" if metadata.kind.is_none() => metadata.kind = Some(text),
+            "T" | "Z" if metadata.title.is_none() => metadata.title = Some(text),
+            "Y" => { metadata.signatory.get_or_insert(text); },
+            //в старых документах подпись в `p.I`, а место, дата и номер в параграфах без класса
+            "I" | "" if SIGNATORY_RX.is_match(&text) => { metadata.signatory.get_or_insert(text); },
+            "C" => metadata.amended_by.extend(p.amendments.iter().cloned()),
+            "I" => metadata.set_signature_line(text),
+            "" if metadata.signatory.is_some() => metadata.set_signature_line(text),
+            "" =>
+            {
+                if let (Some(id), Some(first)) = (p.id, self.first_content_id) && id < first
+                {
+                    self.preamble.push(text);
+                }
+            }
+            _ => ()
+        }
+    }
+    pub(crate) fn finish(mut self) -> DocumentMetadata
+    {
+        if !self.preamble.is_empty()
+        {
+            self.metadata.preamble = Some(self.preamble.join("\n"));
+        }
+        self.metadata
+    }
+}
+
+///"Принят Государственной Думой ...", "Одобрен Советом Федерации ..." - иногда такие строки идут без класса `p.I`
+pub(crate) fn is_adoption_line(text: &str) -> bool
+{
+    ADOPTION_RX.is_match(text)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use utilites::DateFormat;
+    use super::DocumentMetadata;
+
+    #[test]
+    fn test_parse_metadata()
+    {
+        let html = r#"<p class="C" id="p1">РОССИЙСКАЯ ФЕДЕРАЦИЯ</p><p class="T" id="p2">ФЕДЕРАЛЬНЫЙ КОНСТИТУЦИОННЫЙ ЗАКОН</p><p class="Z" id="p3">О принятии в Российскую Федерацию Донецкой Народной Республики</p>
+        <p id="p4">Одобрен Государственной Думой                3 октября 2022 года</p><p class="I" id="p5">Одобрен Советом Федерации                4 октября 2022 года</p>
+        <p class="C" id="p450"><span class="markx">(В редакции федеральных конституционных законов <span cmdprm="gohash=6cae422689d86593934b89f35c4515536bb9820c1e6c39dd4661577ae5149180 goparaid=0 goback=1" class="cmd">от 28.04.2023 № 1-ФКЗ</span>)</span></p>
+        <p id="p6">Статья 1</p>
+        <p class="Y" id="p90">Президент Российской Федерации В.Путин</p><p class="I" id="p91">Москва, Кремль</p><p class="I" id="p92">4 октября 2022 года</p><p class="I" id="p93">№ 5-ФКЗ</p>"#;
+        let metadata = DocumentMetadata::from_html(html);
+        assert_eq!(metadata.kind.as_deref(), Some("ФЕДЕРАЛЬНЫЙ КОНСТИТУЦИОННЫЙ ЗАКОН"));
+        assert_eq!(metadata.duma_date.unwrap().format(DateFormat::DotDate), "03.10.2022");
+        assert_eq!(metadata.council_date.unwrap().format(DateFormat::DotDate), "04.10.2022");
+        assert_eq!(metadata.signatory.as_deref(), Some("Президент Российской Федерации В.Путин"));
+        assert_eq!(metadata.place.as_deref(), Some("Москва, Кремль"));
+        assert_eq!(metadata.sign_date.unwrap().format(DateFormat::DotDate), "04.10.2022");
+        assert_eq!(metadata.number.as_deref(), Some("5-ФКЗ"));
+        assert_eq!(metadata.amended_by.len(), 1);
+        assert!(metadata.preamble.is_none());
+    }
+
+    #[test]
+    fn test_parse_old_signature()
+    {
+        let html = r#"<p class="T">ФЕДЕРАЛЬНЫЙ ЗАКОН</p><p class="T">Об образовании в Российской Федерации</p>
+        <p class="I">Принят Государственной Думой 21 декабря 2012 года</p><p>Статья 1</p>
+        <p class="I">Президент Российской Федерации В.Путин</p><p>Москва, Кремль</p><p>29 декабря 2012 года</p><p>№ 273-ФЗ</p>"#;
+        let metadata = DocumentMetadata::from_html(html);
+        assert_eq!(metadata.title.as_deref(), Some("Об образовании в Российской Федерации"));
+        assert_eq!(metadata.duma_date.unwrap().format(DateFormat::DotDate), "21.12.2012");
+        assert_eq!(metadata.place.as_deref(), Some("Москва, Кремль"));
+        assert_eq!(metadata.sign_date.unwrap().format(DateFormat::DotDate), "29.12.2012");
+        assert_eq!(metadata.number.as_deref(), Some("273-ФЗ"));
+    }
+}
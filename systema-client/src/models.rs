@@ -1,7 +1,8 @@
-use std::sync::LazyLock;
+use std::{ops::Deref, sync::LazyLock};
 
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 use utilites::{empty_string_as_none, null_string_as_none, Date, deserialize_date};
 
 use crate::Error;
@@ -45,7 +46,8 @@ pub struct Redaction
     pub state_class: u32,
     ///"Действует с изменениями"
     #[serde(rename="statename")]
-    pub state: String,
+    #[serde(deserialize_with = "state_from_name")]
+    pub state: DocumentState,
     #[serde(rename="redelements")]
     pub elements:	i16,
     #[serde(rename="redtype")]
@@ -53,7 +55,7 @@ pub struct Redaction
     /// 0 - редакции которые вступают в силу при насуплении указанной даты (date_time)  
     /// 1 - редакции которые вступают в силу при наступлении неизвестной даты для них установлено значение date_time = 20990101  
     /// 2 - зомби-редакции, которые не вступают в силу при наступлении указанной даты, они не считаются последующими или будущими редакциями документа, их как бы нет. 
-    pub redaction_type: u32,
+    pub redaction_type: i32,
     ///	" актуальная"
     #[serde(rename="redcaption")]
     pub caption: String,
@@ -151,6 +153,83 @@ pub struct SystemaDocumentCard
 
 
 
+///Правовое состояние документа (`docstate` в карточке документа)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DocumentState
+{
+    ///"Действует", "Действует без изменений"
+    InForce,
+    ///"Действует с изменениями"
+    InForceWithChanges,
+    ///"Не вступил в силу"
+    NotYetInForce,
+    ///"Приостановлен"
+    Suspended,
+    ///"Утратил силу"
+    Repealed,
+    #[default]
+    Unknown
+}
+impl From<&str> for DocumentState
+{
+    fn from(value: &str) -> Self
+    {
+        let value = value.trim().to_lowercase();
+        if value.starts_with("утратил")
+        {
+            Self::Repealed
+        }
+        else if value.starts_with("не вступ")
+        {
+            Self::NotYetInForce
+        }
+        else if value.starts_with("приостановлен")
+        {
+            Self::Suspended
+        }
+        else if value.starts_with("действует с изменениями")
+        {
+            Self::InForceWithChanges
+        }
+        else if value.starts_with("действует")
+        {
+            Self::InForce
+        }
+        else
+        {
+            Self::Unknown
+        }
+    }
+}
+///`statename` редакции строкой в `DocumentState`
+fn state_from_name<'de, D>(deserializer: D) -> Result<DocumentState, D::Error>
+where D: serde::Deserializer<'de>
+{
+    let name = String::deserialize(deserializer)?;
+    Ok(name.as_str().into())
+}
+impl DocumentState
+{
+    ///Документ утратил силу
+    pub fn is_repealed(&self) -> bool
+    {
+        *self == Self::Repealed
+    }
+    ///Значение как в сериализованном виде, для фильтров по payload
+    pub fn as_str(&self) -> &'static str
+    {
+        match self
+        {
+            Self::InForce => "InForce",
+            Self::InForceWithChanges => "InForceWithChanges",
+            Self::NotYetInForce => "NotYetInForce",
+            Self::Suspended => "Suspended",
+            Self::Repealed => "Repealed",
+            Self::Unknown => "Unknown"
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct SystemaTextResponse
 {
@@ -165,6 +244,98 @@ pub struct SystemaTextResponse
 
 
 
+// id: 455520,
+// ref_id: 307408,
+// date: Date(2025-03-01T00:00:00),
+// date_time: Date(2025-03-01T05:37:30),
+// state_id: 3,
+// state_class: 3,
+// state: "Действует с изменениями",
+// elements: 925,
+// redaction_type: 0,
+// caption: "153. на 01.03.2025 (№ 171-ФЗ от 08.07.2024), с изменениями, не вступившими в силу",
+// status: "не вступившая",
+// reason: None,
+// flag: 58,
+// is_completed: true,
+// is_checked: false,
+// is_official: false,
+// is_actual: false,
+// is_initial: false,
+// is_has_content: true,
+// is_content_complete: true
+///Тип редакции по моменту вступления в силу (`redtype`)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RedactionType
+{
+    ///-1 - редакции которые вступают в силу ранее даты подписания
+    Retroactive,
+    /// 0 - редакции которые вступают в силу при насуплении указанной даты
+    Scheduled,
+    /// 1 - редакции которые вступают в силу при наступлении неизвестной даты, для них api отдает дату 20990101
+    UnknownDate,
+    /// 2 - зомби-редакции, которые не вступают в силу при наступлении указанной даты, они не считаются последующими или будущими редакциями документа, их как бы нет
+    Zombie,
+    ///тип которого нет в api на момент написания, дату вступления в силу такой редакции не знаем и в хронологию она не попадает
+    Unknown(i32)
+}
+impl From<i32> for RedactionType
+{
+    fn from(value: i32) -> Self
+    {
+        match value
+        {
+            -1 => Self::Retroactive,
+            1 => Self::UnknownDate,
+            0 => Self::Scheduled,
+            2 => Self::Zombie,
+            other =>
+            {
+                warn!("неизвестный тип редакции `{}`", other);
+                Self::Unknown(other)
+            }
+        }
+    }
+}
+
+///Статус редакции (`redstatus`)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RedactionStatus
+{
+    ///"актуальная", "действующая"
+    Actual,
+    ///"не действующая"
+    Inactive,
+    ///"не вступившая"
+    NotYetInForce,
+    ///статус который мы не знаем, такой редакции не доверяем ни как действующей ни как утратившей силу
+    Unknown
+}
+impl From<&str> for RedactionStatus
+{
+    fn from(value: &str) -> Self
+    {
+        let value = value.trim().to_lowercase();
+        if value.contains("не вступ")
+        {
+            Self::NotYetInForce
+        }
+        else if value.starts_with("не") || value.starts_with("утрат")
+        {
+            Self::Inactive
+        }
+        else if value.starts_with("актуальн") || value.starts_with("действ")
+        {
+            Self::Actual
+        }
+        else
+        {
+            warn!("неизвестный статус редакции `{}`", value);
+            Self::Unknown
+        }
+    }
+}
+
 // id: 455520,
 // ref_id: 307408,
 // date: Date(2025-03-01T00:00:00),
@@ -195,17 +366,12 @@ pub struct ExtendedRedaction
     /// 
     pub state_id: u32,
     ///"Действует с изменениями"
-    pub state: String,
+    pub state: DocumentState,
     pub elements:	i16,
-    ///-1 - редакции которые вступают в силу ранее даты подписания которая храниться в поле date_time  
-    /// 0 - редакции которые вступают в силу при насуплении указанной даты (date_time)  
-    /// 1 - редакции которые вступают в силу при наступлении неизвестной даты для них установлено значение date_time = 20990101  
-    /// 2 - зомби-редакции, которые не вступают в силу при наступлении указанной даты, они не считаются последующими или будущими редакциями документа, их как бы нет. 
-    pub redaction_type: u32,
+    pub redaction_type: RedactionType,
     ///	" актуальная"
     pub caption: String,
-    ///	"актуальная" недействующая, действующая не всупившая
-    pub status: String,
+    pub status: RedactionStatus,
     ///57
     pub flag:	u32,
     pub is_actual: bool,
@@ -213,6 +379,19 @@ pub struct ExtendedRedaction
     pub source_number: Option<String>,
     ///дата документа который вносит изменение
     pub source_date: Option<Date>,
+    ///дата с которой редакция действует, для редакций с неизвестной датой вступления и зомби-редакций `None`
+    pub effective_from: Option<Date>,
+    ///дата вступления в силу следующей редакции, `None` если редакция последняя (или дата вступления неизвестна)
+    pub effective_to: Option<Date>,
+}
+impl ExtendedRedaction
+{
+    ///Действовал ли текст этой редакции на указанную дату
+    pub fn is_in_force_on(&self, date: &Date) -> bool
+    {
+        self.effective_from.as_ref().is_some_and(|from| from <= date)
+        && self.effective_to.as_ref().is_none_or(|to| date < to)
+    }
 }
 static REDACTIONS_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\d{1,}[.]\s+([*]\s+)?\w{2}\s+(?<red_date>\d{2}[.]\d{2}[.]\d{4})\s+[(](№\s+(?<source_number>\d{1,}-[ФЗК]+)(\s+\w{2}\s+(?<source_date>\d{2}[.]\d{2}[.]\d{4}))?)[)]").unwrap());
 impl From<Redaction> for ExtendedRedaction
@@ -229,6 +408,12 @@ impl From<Redaction> for ExtendedRedaction
         {
             (None, None)
         };
+        let redaction_type: RedactionType = value.redaction_type.into();
+        let effective_from = match redaction_type
+        {
+            RedactionType::UnknownDate | RedactionType::Zombie | RedactionType::Unknown(_) => None,
+            _ => Some(value.date.clone())
+        };
         Self 
         { 
             id: value.id,
@@ -236,20 +421,92 @@ impl From<Redaction> for ExtendedRedaction
             state_id: value.state_id,
             state: value.state,
             elements: value.elements,
-            redaction_type: value.redaction_type,
+            redaction_type,
+            status: value.status.as_str().into(),
             caption: value.caption,
-            status: value.status,
             flag: value.flag,
             is_actual: value.is_actual,
             source_date: captures.0,
-            source_number: captures.1
+            source_number: captures.1,
+            effective_from,
+            effective_to: None
         }
     }
 }
 
-///http://actual.pravo.gov.ru:8000/api/ebpi/getcontent/?bpa=ebpi&rdk=483442
-/// содержание
+///Список редакций документа упорядоченный по дате,
+/// при создании для каждой редакции вычисляется период действия (`effective_from` - `effective_to`)
 #[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Redactions
+{
+    redactions: Vec<ExtendedRedaction>,
+    ///хеш документа (`dochash`), по нему же можно снова запросить редакции
+    #[serde(default)]
+    hash: String
+}
+impl Redactions
+{
+    pub fn new(mut redactions: Vec<ExtendedRedaction>) -> Self
+    {
+        redactions.sort_by(|a, b| a.date.cmp(&b.date).then(a.id.cmp(&b.id)));
+        let starts: Vec<Option<Date>> = redactions.iter().map(|r| r.effective_from.clone()).collect();
+        for (i, redaction) in redactions.iter_mut().enumerate()
+        {
+            if let Some(from) = redaction.effective_from.as_ref()
+            {
+                redaction.effective_to = starts[i + 1..].iter().flatten().find(|next| *next > from).cloned();
+            }
+        }
+        Self { redactions, hash: String::new() }
+    }
+    pub(crate) fn with_hash(mut self, hash: String) -> Self
+    {
+        self.hash = hash;
+        self
+    }
+    pub fn hash(&self) -> &str
+    {
+        &self.hash
+    }
+    ///Редакция текст которой действовал на указанную дату  
+    /// если на одну дату приходится несколько редакций берется последняя из них
+    pub fn in_force_on(&self, date: &Date) -> Option<&ExtendedRedaction>
+    {
+        self.redactions.iter().filter(|r| r.is_in_force_on(date)).last()
+    }
+    ///Дата ближайшего изменения текста после указанной даты
+    pub fn next_change_after(&self, date: &Date) -> Option<&Date>
+    {
+        self.redactions.iter().filter_map(|r| r.effective_from.as_ref()).find(|from| *from > date)
+    }
+    ///Актуальная редакция (флаг `actual` из api)
+    pub fn actual(&self) -> Option<&ExtendedRedaction>
+    {
+        self.redactions.iter().find(|r| r.is_actual)
+    }
+}
+impl Deref for Redactions
+{
+    type Target = [ExtendedRedaction];
+    fn deref(&self) -> &Self::Target
+    {
+        &self.redactions
+    }
+}
+impl IntoIterator for Redactions
+{
+    type Item = ExtendedRedaction;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+    fn into_iter(self) -> Self::IntoIter
+    {
+        self.redactions.into_iter()
+    }
+}
+
+///http://actual.pravo.gov.ru:8000/api/ebpi/getcontent/?bpa=ebpi&rdk=483442
+/// содержание, у документов не из ebpi оно пустое
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
 pub struct Contents
 {
     #[serde(rename="data")]
@@ -321,6 +578,78 @@ pub struct ContentItem
 #[cfg(test)]
 mod tests
 {
+    use utilites::Date;
+    use super::{DocumentState, ExtendedRedaction, RedactionStatus, RedactionType, Redactions};
 
-   
+    fn redaction(id: u32, date: Date, redaction_type: RedactionType, status: RedactionStatus) -> ExtendedRedaction
+    {
+        ExtendedRedaction
+        {
+            id,
+            effective_from: match redaction_type
+            {
+                RedactionType::UnknownDate | RedactionType::Zombie | RedactionType::Unknown(_) => None,
+                _ => Some(date.clone())
+            },
+            date,
+            state_id: 3,
+            state: DocumentState::InForceWithChanges,
+            elements: 0,
+            redaction_type,
+            caption: String::new(),
+            status,
+            flag: 0,
+            is_actual: status == RedactionStatus::Actual,
+            source_number: None,
+            source_date: None,
+            effective_to: None
+        }
+    }
+
+    #[test]
+    fn test_status()
+    {
+        assert_eq!(RedactionStatus::from("актуальная"), RedactionStatus::Actual);
+        assert_eq!(RedactionStatus::from(" не действующая"), RedactionStatus::Inactive);
+        assert_eq!(RedactionStatus::from("не вступившая"), RedactionStatus::NotYetInForce);
+        assert_eq!(RedactionStatus::from("черновик"), RedactionStatus::Unknown);
+        assert_eq!(RedactionType::from(-1), RedactionType::Retroactive);
+        assert_eq!(RedactionType::from(2), RedactionType::Zombie);
+        assert_eq!(RedactionType::from(7), RedactionType::Unknown(7));
+    }
+
+    #[test]
+    fn test_document_state()
+    {
+        assert_eq!(DocumentState::from("Действует с изменениями"), DocumentState::InForceWithChanges);
+        assert_eq!(DocumentState::from("Действует"), DocumentState::InForce);
+        assert_eq!(DocumentState::from("Утратил силу"), DocumentState::Repealed);
+        assert_eq!(DocumentState::from("Не вступил в силу"), DocumentState::NotYetInForce);
+        assert_eq!(DocumentState::from(""), DocumentState::Unknown);
+        assert!(DocumentState::from("Утратил силу").is_repealed());
+        for state in [DocumentState::InForce, DocumentState::InForceWithChanges, DocumentState::NotYetInForce, DocumentState::Suspended, DocumentState::Repealed, DocumentState::Unknown]
+        {
+            assert_eq!(serde_json::to_value(state).unwrap(), state.as_str());
+        }
+    }
+
+    #[test]
+    fn test_in_force_on()
+    {
+        let redactions = Redactions::new(vec![
+            redaction(3, Date::new_date(1, 3, 2025), RedactionType::Scheduled, RedactionStatus::NotYetInForce),
+            redaction(1, Date::new_date(1, 1, 2020), RedactionType::Scheduled, RedactionStatus::Inactive),
+            redaction(4, Date::new_date(1, 1, 2099), RedactionType::UnknownDate, RedactionStatus::NotYetInForce),
+            redaction(2, Date::new_date(1, 9, 2023), RedactionType::Scheduled, RedactionStatus::Actual),
+            redaction(5, Date::new_date(1, 1, 2022), RedactionType::Unknown(7), RedactionStatus::Unknown),
+        ]);
+        //редакция неизвестного типа не прерывает действие предыдущей и не считается изменением
+        assert_eq!(redactions.in_force_on(&Date::new_date(15, 5, 2021)).map(|r| r.id), Some(1));
+        assert_eq!(redactions.in_force_on(&Date::new_date(2, 1, 2022)).map(|r| r.id), Some(1));
+        assert_eq!(redactions.in_force_on(&Date::new_date(1, 9, 2023)).map(|r| r.id), Some(2));
+        assert_eq!(redactions.in_force_on(&Date::new_date(2, 3, 2025)).map(|r| r.id), Some(3));
+        assert_eq!(redactions.in_force_on(&Date::new_date(1, 1, 2019)).map(|r| r.id), None);
+        assert_eq!(redactions.next_change_after(&Date::new_date(15, 5, 2021)).map(|d| d.format(utilites::DateFormat::DotDate)), Some("01.09.2023".to_owned()));
+        assert_eq!(redactions.actual().map(|r| r.id), Some(2));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,279 @@
+use std::fmt::Debug;
+use serde::{Deserialize, Serialize};
+use crate::{document::DocumentNodes, report::{self, Entry, GroupKey}, stream::escape};
+
+const STYLE: &str = "body{font-family:serif;max-width:1200px;margin:auto}\
+ins{background:#d4f7d4;text-decoration:none}del{background:#f7d4d4}\
+table{width:100%;border-collapse:collapse}td{vertical-align:top;width:50%;padding:4px;border-bottom:1px solid #eee}\
+.added{border-left:3px solid #3a3}.removed{border-left:3px solid #c33}.changed{border-left:3px solid #fa0}";
+
+///Вид сравнения редакций
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RedlineMode
+{
+    ///изменения выделены прямо в тексте новой редакции
+    Inline,
+    ///предыдущая редакция слева, новая справа
+    SideBySide
+}
+
+///Html документ со сравнением двух редакций, вставки и удаления выделены по словам
+/// в документ попадают только измененные статьи, у каждой якорь по заголовку статьи и ссылка в оглавлении
+pub fn render_redline<C: ToString + Debug>(previous: &DocumentNodes<C>, current: &DocumentNodes<C>, mode: RedlineMode) -> String
+{
+    let old_groups = report::groups(previous);
+    let new_groups = report::groups(current);
+    let pairs: Vec<(&GroupKey, &[Entry<'_, C>], &[Entry<'_, C>])> = new_groups.iter()
+        .map(|(key, new)| (key, old_groups.iter().find(|(k, _)| k == key).map(|(_, e)| e.as_slice()).unwrap_or_default(), new.as_slice()))
+        //статьи которых в новой редакции нет совсем
+        .chain(old_groups.iter().filter(|(k, _)| !new_groups.iter().any(|(n, _)| n == k)).map(|(key, old)| (key, old.as_slice(), &[][..])))
+        .collect();
+    //(якорь, заголовок, html статьи)
+    let mut sections: Vec<(String, String, String)> = Vec::new();
+    for (i, (key, old, new)) in pairs.into_iter().enumerate()
+    {
+        if let Some(body) = render_group(old, new, mode)
+        {
+            let anchor = key.1.as_ref().or(key.0.as_ref())
+                .map(|c| c.replace(char::is_whitespace, "-"))
+                .unwrap_or(["g", &i.to_string()].concat());
+            sections.push((anchor, group_title(key), body));
+        }
+    }
+    let title = escape(current.title(), false);
+    let mut html = ["<!DOCTYPE html>\n<html lang=\"ru\"><head><meta charset=\"utf-8\"><title>", &title, "</title><style>", STYLE, "</style></head><body>\n"].concat();
+    html.push_str(&["<h1>", &title, " № ", &escape(current.number(), false), "</h1>\n"].concat());
+    html.push_str(&["<p>Редакция ", &previous.redaction_id().to_string(), " → редакция ", &current.redaction_id().to_string(), ", изменено статей: ", &sections.len().to_string(), "</p>\n<nav><ul>\n"].concat());
+    for (anchor, title, _) in &sections
+    {
+        html.push_str(&["<li><a href=\"#", &escape(anchor, true), "\">", &escape(title, false), "</a></li>\n"].concat());
+    }
+    html.push_str("</ul></nav>\n");
+    for (anchor, title, body) in &sections
+    {
+        html.push_str(&["<section id=\"", &escape(anchor, true), "\"><h2>", &escape(title, false), "</h2>\n", body, "</section>\n"].concat());
+    }
+    html.push_str("</body></html>\n");
+    html
+}
+
+fn group_title(key: &GroupKey) -> String
+{
+    match key
+    {
+        (Some(chapter), Some(article)) => [chapter, " / ", article].concat(),
+        (None, Some(caption)) | (Some(caption), None) => caption.clone(),
+        (None, None) => "Положения вне статей".to_owned()
+    }
+}
+
+///Html статьи, `None` если статья не изменилась
+fn render_group<C: ToString + Debug>(old: &[Entry<'_, C>], new: &[Entry<'_, C>], mode: RedlineMode) -> Option<String>
+{
+    let old_texts: Vec<&str> = old.iter().map(|e| e.text.as_str()).collect();
+    let new_texts: Vec<&str> = new.iter().map(|e| e.text.as_str()).collect();
+    let pairs = report::align(&old_texts, &new_texts);
+    if pairs.iter().all(|(o, n)| matches!((o, n), (Some(o), Some(n)) if old_texts[*o] == new_texts[*n]))
+    {
+        return None;
+    }
+    let mut body = String::new();
+    if mode == RedlineMode::SideBySide
+    {
+        body.push_str("<table>\n");
+    }
+    for (o, n) in pairs
+    {
+        let (o, n) = (o.map(|i| old_texts[i]), n.map(|i| new_texts[i]));
+        body.push_str(&match mode
+        {
+            RedlineMode::Inline => inline_row(o, n),
+            RedlineMode::SideBySide => side_row(o, n)
+        });
+    }
+    if mode == RedlineMode::SideBySide
+    {
+        body.push_str("</table>\n");
+    }
+    Some(body)
+}
+
+fn inline_row(old: Option<&str>, new: Option<&str>) -> String
+{
+    match (old, new)
+    {
+        (Some(o), Some(n)) if o == n => ["<p>", &text_html(n), "</p>\n"].concat(),
+        (Some(o), Some(n)) => ["<p class=\"changed\">", &word_diff(o, n).inline, "</p>\n"].concat(),
+        (None, Some(n)) => ["<p class=\"added\"><ins>", &text_html(n), "</ins></p>\n"].concat(),
+        (Some(o), None) => ["<p class=\"removed\"><del>", &text_html(o), "</del></p>\n"].concat(),
+        (None, None) => String::new()
+    }
+}
+
+fn side_row(old: Option<&str>, new: Option<&str>) -> String
+{
+    match (old, new)
+    {
+        (Some(o), Some(n)) if o == n =>
+        {
+            let text = text_html(n);
+            ["<tr><td>", &text, "</td><td>", &text, "</td></tr>\n"].concat()
+        }
+        (Some(o), Some(n)) =>
+        {
+            let diff = word_diff(o, n);
+            ["<tr class=\"changed\"><td>", &diff.old, "</td><td>", &diff.new, "</td></tr>\n"].concat()
+        }
+        (None, Some(n)) => ["<tr class=\"added\"><td></td><td><ins>", &text_html(n), "</ins></td></tr>\n"].concat(),
+        (Some(o), None) => ["<tr class=\"removed\"><td><del>", &text_html(o), "</del></td><td></td></tr>\n"].concat(),
+        (None, None) => String::new()
+    }
+}
+
+fn text_html(text: &str) -> String
+{
+    escape(text, false).replace('\n', "<br>\n")
+}
+
+///Разница текстов по словам
+#[derive(Default)]
+struct WordDiff
+{
+    ///старый текст с `<del>`
+    old: String,
+    ///новый текст с `<ins>`
+    new: String,
+    ///общий текст с `<del>` и `<ins>`
+    inline: String
+}
+impl WordDiff
+{
+    fn push_same(&mut self, word: &str)
+    {
+        let word = text_html(word);
+        self.old.push_str(&word);
+        self.new.push_str(&word);
+        self.inline.push_str(&word);
+    }
+    ///подряд идущие удаленные и вставленные слова выделяются одним блоком
+    fn flush(&mut self, deleted: &mut String, inserted: &mut String)
+    {
+        if !deleted.is_empty()
+        {
+            let del = ["<del>", &text_html(deleted), "</del>"].concat();
+            self.old.push_str(&del);
+            self.inline.push_str(&del);
+            deleted.clear();
+        }
+        if !inserted.is_empty()
+        {
+            let ins = ["<ins>", &text_html(inserted), "</ins>"].concat();
+            self.new.push_str(&ins);
+            self.inline.push_str(&ins);
+            inserted.clear();
+        }
+    }
+}
+
+fn word_diff(old: &str, new: &str) -> WordDiff
+{
+    let old_words = words(old);
+    let new_words = words(new);
+    let mut diff = WordDiff::default();
+    let mut deleted = String::new();
+    let mut inserted = String::new();
+    for (o, n) in report::align(&old_words, &new_words)
+    {
+        match (o, n)
+        {
+            (Some(o), Some(n)) if old_words[o] == new_words[n] =>
+            {
+                diff.flush(&mut deleted, &mut inserted);
+                diff.push_same(old_words[o]);
+            }
+            _ =>
+            {
+                if let Some(o) = o
+                {
+                    deleted.push_str(old_words[o]);
+                }
+                if let Some(n) = n
+                {
+                    inserted.push_str(new_words[n]);
+                }
+            }
+        }
+    }
+    diff.flush(&mut deleted, &mut inserted);
+    diff
+}
+
+///Слова, пробелы и знаки препинания по отдельности, склеенные обратно они дают исходный текст
+fn words(text: &str) -> Vec<&str>
+{
+    //0 - пробелы, 1 - буквы и цифры, 2 - остальное, каждый такой символ отдельно
+    let class = |c: char| if c.is_whitespace() { 0 } else if c.is_alphanumeric() { 1 } else { 2 };
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut prev: Option<u8> = None;
+    for (i, c) in text.char_indices()
+    {
+        let current = class(c);
+        if prev.is_some_and(|p| p != current || p == 2)
+        {
+            words.push(&text[start..i]);
+            start = i;
+        }
+        prev = Some(current);
+    }
+    if start < text.len()
+    {
+        words.push(&text[start..]);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::document::{DocumentNode, DocumentNodes};
+    use super::RedlineMode;
+
+    fn document(paragraphs: &[&str]) -> DocumentNodes<String>
+    {
+        let mut nodes = DocumentNodes::default();
+        nodes.insert(DocumentNode::new("статья", String::new(), "Статья 1. Права".to_owned(), None, 1, 1 + paragraphs.len(), 0, "$Статья 1"));
+        for (i, text) in paragraphs.iter().enumerate()
+        {
+            nodes.insert(DocumentNode::new("параграф", String::new(), text.to_string(), None, 2 + i, 2 + i, 1, "параграф"));
+        }
+        nodes.insert(DocumentNode::new("статья", String::new(), "Статья 2. Обязанности".to_owned(), None, 10, 10, 0, "$Статья 2"));
+        nodes
+    }
+
+    #[test]
+    fn test_word_diff()
+    {
+        assert_eq!(super::words("1. Первая  часть"), vec!["1", ".", " ", "Первая", "  ", "часть"]);
+        let diff = super::word_diff("1. Первая часть <a>.", "1. Первая новая часть <a>.");
+        assert_eq!(diff.inline, "1. Первая <ins>новая </ins>часть &lt;a&gt;.");
+        assert_eq!(diff.old, "1. Первая часть &lt;a&gt;.");
+        let diff = super::word_diff("срок три года", "срок пять лет");
+        assert_eq!(diff.inline, "срок <del>три</del><ins>пять</ins> <del>года</del><ins>лет</ins>");
+    }
+
+    #[test]
+    fn test_render_redline()
+    {
+        let previous = document(&["1. Первая часть.", "2. Вторая часть."]);
+        let current = document(&["1. Первая часть в новой редакции.", "2. Вторая часть.", "3. Третья часть."]);
+        let html = super::render_redline(&previous, &current, RedlineMode::Inline);
+        assert!(html.contains("<a href=\"#Статья-1\">"));
+        assert!(html.contains("<section id=\"Статья-1\">"));
+        assert!(!html.contains("Статья-2"));
+        assert!(html.contains("<p class=\"changed\">1. Первая часть<ins> в новой редакции</ins>.</p>"));
+        assert!(html.contains("<p class=\"added\"><ins>3. Третья часть.</ins></p>"));
+        let html = super::render_redline(&previous, &current, RedlineMode::SideBySide);
+        assert!(html.contains("<tr class=\"added\"><td></td>"));
+    }
+}
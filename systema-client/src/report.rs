@@ -0,0 +1,353 @@
+use std::fmt::Debug;
+use serde::Serialize;
+use utilites::{Date, DateFormat};
+use crate::{annotations::AmendingAct, document::{DocumentNode, DocumentNodes}, models::ExtendedRedaction};
+
+///(глава или раздел, статья)
+pub(crate) type GroupKey = (Option<String>, Option<String>);
+
+///Вид изменения узла в новой редакции
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind
+{
+    Added,
+    Modified,
+    ///узел утратил силу или его нет в новой редакции
+    Repealed
+}
+impl ChangeKind
+{
+    fn name(&self) -> &'static str
+    {
+        match self
+        {
+            Self::Added => "Добавлено",
+            Self::Modified => "Изменено",
+            Self::Repealed => "Утратило силу"
+        }
+    }
+}
+
+///Изменившийся узел документа
+#[derive(Debug, Serialize, Clone)]
+pub struct NodeChange
+{
+    pub kind: ChangeKind,
+    ///глава (если глав нет - раздел) в которой находится узел
+    pub chapter: Option<String>,
+    pub article: Option<String>,
+    ///заголовок узла из содержания, у параграфов `None`
+    pub caption: Option<String>,
+    ///текст в предыдущей редакции
+    pub before: Option<String>,
+    ///текст в новой редакции
+    pub after: Option<String>,
+    ///документы внесшие изменение, из аннотаций узла в новой редакции
+    pub acts: Vec<AmendingAct>,
+    ///ссылка на узел в новой редакции, для удаленных узлов - в предыдущей
+    pub url: String
+}
+
+///Сводка изменений новой редакции документа по сравнению с предыдущей
+#[derive(Debug, Serialize, Clone)]
+pub struct RedactionReport
+{
+    pub name: String,
+    pub number: String,
+    pub redaction: ExtendedRedaction,
+    pub previous_redaction_id: u32,
+    ///изменения в порядке документа, сгруппированы по главам и статьям
+    pub changes: Vec<NodeChange>
+}
+
+pub(crate) struct Entry<'a, C: ToString + Debug>
+{
+    pub node: &'a DocumentNode<C>,
+    pub text: String
+}
+
+impl RedactionReport
+{
+    ///Сравнение деревьев двух редакций, узлы сопоставляются внутри статей по тексту
+    pub fn compare<C: ToString + Debug>(previous: &DocumentNodes<C>, current: &DocumentNodes<C>, redaction: ExtendedRedaction) -> Self
+    {
+        let old_groups = groups(previous);
+        let new_groups = groups(current);
+        let mut changes = Vec::new();
+        for (key, new) in &new_groups
+        {
+            let old = old_groups.iter().find(|(k, _)| k == key).map(|(_, e)| e.as_slice()).unwrap_or_default();
+            diff_group(key, (previous, old), (current, new.as_slice()), &redaction, &mut changes);
+        }
+        //статьи которых в новой редакции нет совсем
+        for (key, old) in old_groups.iter().filter(|(k, _)| !new_groups.iter().any(|(n, _)| n == k))
+        {
+            diff_group(key, (previous, old.as_slice()), (current, &[][..]), &redaction, &mut changes);
+        }
+        Self
+        {
+            name: current.title().to_owned(),
+            number: current.number().to_owned(),
+            redaction,
+            previous_redaction_id: previous.redaction_id(),
+            changes
+        }
+    }
+    ///Сводка для рассылки: заголовки глав и статей, под ними изменения с текстом до и после
+    pub fn to_markdown(&self) -> String
+    {
+        let mut md = ["# ", &self.name, " № ", &self.number, "\n\n"].concat();
+        md.push_str(&["Редакция от ", &self.redaction.date.format(DateFormat::DotDate)].concat());
+        if let Some(number) = &self.redaction.source_number
+        {
+            md.push_str(&[" (изменения внесены документом № ", number, &date_suffix(self.redaction.source_date.as_ref()), ")"].concat());
+        }
+        md.push_str(&[", изменений: ", &self.changes.len().to_string(), "\n"].concat());
+        let mut chapter: Option<&Option<String>> = None;
+        let mut article: Option<&Option<String>> = None;
+        for change in &self.changes
+        {
+            if chapter != Some(&change.chapter)
+            {
+                chapter = Some(&change.chapter);
+                article = None;
+                if let Some(c) = &change.chapter
+                {
+                    md.push_str(&["\n## ", c, "\n"].concat());
+                }
+            }
+            if article != Some(&change.article)
+            {
+                article = Some(&change.article);
+                if let Some(a) = &change.article
+                {
+                    md.push_str(&["\n### ", a, "\n"].concat());
+                }
+            }
+            md.push_str(&["\n**", change.kind.name(), "**"].concat());
+            if let Some(caption) = &change.caption
+            {
+                md.push_str(&[" ", caption].concat());
+            }
+            let acts: Vec<String> = change.acts.iter()
+                .filter_map(|a| a.number.as_ref().map(|n| ["№ ", n, &date_suffix(a.date.as_ref())].concat()))
+                .collect();
+            if !acts.is_empty()
+            {
+                md.push_str(&[" (", &acts.join("; "), ")"].concat());
+            }
+            md.push_str(&[" [текст](", &change.url, ")\n"].concat());
+            if let Some(before) = &change.before
+            {
+                md.push_str(&["\nБыло:\n", &quote(before)].concat());
+            }
+            if let Some(after) = &change.after
+            {
+                md.push_str(&["\nСтало:\n", &quote(after)].concat());
+            }
+        }
+        md
+    }
+}
+
+fn date_suffix(date: Option<&Date>) -> String
+{
+    date.map(|d| [" от ", &d.format(DateFormat::DotDate)].concat()).unwrap_or_default()
+}
+
+fn quote(text: &str) -> String
+{
+    text.lines().map(|l| ["> ", l, "\n"].concat()).collect()
+}
+
+///Узлы документа по статьям, в порядке документа
+pub(crate) fn groups<C: ToString + Debug>(nodes: &DocumentNodes<C>) -> Vec<(GroupKey, Vec<Entry<'_, C>>)>
+{
+    let mut groups: Vec<(GroupKey, Vec<Entry<'_, C>>)> = Vec::new();
+    for node in nodes
+    {
+        let mut path = nodes.find_all_parents_by_node(node);
+        path.push(node);
+        let caption_of = |unit: &str| path.iter().rev()
+            .find(|n| n.content_type().to_lowercase().starts_with(unit))
+            .map(|n| n.caption().trim_start_matches('$').trim().to_owned());
+        let key = (caption_of("глав").or_else(|| caption_of("раздел")), caption_of("стать"));
+        let entry = Entry { node, text: node.text_with_notes() };
+        match groups.last_mut()
+        {
+            Some((k, entries)) if *k == key => entries.push(entry),
+            _ => groups.push((key, vec![entry]))
+        }
+    }
+    groups
+}
+
+fn diff_group<C: ToString + Debug>(key: &GroupKey, previous: (&DocumentNodes<C>, &[Entry<'_, C>]), current: (&DocumentNodes<C>, &[Entry<'_, C>]), redaction: &ExtendedRedaction, changes: &mut Vec<NodeChange>)
+{
+    let (old_nodes, old) = previous;
+    let (new_nodes, new) = current;
+    let old_texts: Vec<&str> = old.iter().map(|e| e.text.as_str()).collect();
+    let new_texts: Vec<&str> = new.iter().map(|e| e.text.as_str()).collect();
+    for (o, n) in align(&old_texts, &new_texts)
+    {
+        let (o, n) = (o.map(|i| &old[i]), n.map(|i| &new[i]));
+        let (kind, nodes, entry) = match (o, n)
+        {
+            (Some(o), Some(n)) if o.text == n.text => continue,
+            (Some(o), Some(n)) if n.node.is_repealed() && !o.node.is_repealed() => (ChangeKind::Repealed, new_nodes, n),
+            (Some(_), Some(n)) => (ChangeKind::Modified, new_nodes, n),
+            (None, Some(n)) => (ChangeKind::Added, new_nodes, n),
+            (Some(o), None) => (ChangeKind::Repealed, old_nodes, o),
+            (None, None) => continue
+        };
+        changes.push(NodeChange
+        {
+            kind,
+            chapter: key.0.clone(),
+            article: key.1.clone(),
+            caption: (entry.node.content_type() != "параграф").then(|| entry.node.caption().trim_start_matches('$').trim().to_owned()),
+            before: o.map(|o| o.text.clone()),
+            after: n.map(|n| n.text.clone()),
+            acts: n.map(|n| amending_acts(n.node, redaction)).unwrap_or_default(),
+            url: nodes.node_url(entry.node)
+        });
+    }
+}
+
+///Документы из аннотаций узла, если у редакции известен изменяющий документ - только он
+fn amending_acts<C: ToString + Debug>(node: &DocumentNode<C>, redaction: &ExtendedRedaction) -> Vec<AmendingAct>
+{
+    let acts = node.amendments().iter().flat_map(|a| a.acts.iter().cloned());
+    match &redaction.source_number
+    {
+        Some(number) => acts.filter(|a| a.number.as_ref() == Some(number)).collect(),
+        None => acts.collect()
+    }
+}
+
+///Сопоставление двух последовательностей по наибольшей общей подпоследовательности
+/// пары индексов (старый, новый), удаленные и добавленные между совпадениями идут парами как измененные,
+/// то что осталось без пары - с `None` с другой стороны
+pub(crate) fn align<T: PartialEq>(old: &[T], new: &[T]) -> Vec<(Option<usize>, Option<usize>)>
+{
+    //общие начало и конец отрезаются сразу, чтобы не строить таблицу по всему документу
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let o = &old[prefix..old.len() - suffix];
+    let n = &new[prefix..new.len() - suffix];
+    let mut lcs = vec![vec![0u32; n.len() + 1]; o.len() + 1];
+    for i in (0..o.len()).rev()
+    {
+        for j in (0..n.len()).rev()
+        {
+            lcs[i][j] = if o[i] == n[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+    let mut result: Vec<(Option<usize>, Option<usize>)> = (0..prefix).map(|i| (Some(i), Some(i))).collect();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < o.len() && j < n.len()
+    {
+        if o[i] == n[j]
+        {
+            flush(&mut removed, &mut added, &mut result);
+            result.push((Some(prefix + i), Some(prefix + j)));
+            i += 1;
+            j += 1;
+        }
+        else if lcs[i + 1][j] >= lcs[i][j + 1]
+        {
+            removed.push(prefix + i);
+            i += 1;
+        }
+        else
+        {
+            added.push(prefix + j);
+            j += 1;
+        }
+    }
+    removed.extend((i..o.len()).map(|i| prefix + i));
+    added.extend((j..n.len()).map(|j| prefix + j));
+    flush(&mut removed, &mut added, &mut result);
+    result.extend((0..suffix).map(|s| (Some(old.len() - suffix + s), Some(new.len() - suffix + s))));
+    result
+}
+
+fn flush(removed: &mut Vec<usize>, added: &mut Vec<usize>, result: &mut Vec<(Option<usize>, Option<usize>)>)
+{
+    let pairs = removed.len().max(added.len());
+    for k in 0..pairs
+    {
+        result.push((removed.get(k).copied(), added.get(k).copied()));
+    }
+    removed.clear();
+    added.clear();
+}
+
+#[cfg(test)]
+mod tests
+{
+    use utilites::Date;
+    use crate::{annotations::Amendment, document::{DocumentNode, DocumentNodes}, models::{DocumentState, ExtendedRedaction, RedactionStatus, RedactionType}};
+    use super::{ChangeKind, RedactionReport};
+
+    fn article(nodes: &mut DocumentNodes<String>, start: usize, caption: &str, paragraphs: &[(&str, Option<&str>)])
+    {
+        nodes.insert(DocumentNode::new("статья", String::new(), [caption, ". Права"].concat(), None, start, start + paragraphs.len(), 0, &["$", caption].concat()));
+        for (i, (text, amendment)) in paragraphs.iter().enumerate()
+        {
+            let amendments: Vec<Amendment> = amendment.iter().map(|a| Amendment::parse(a, &[])).collect();
+            let repealed = amendments.iter().any(|a| a.is_repeal());
+            nodes.insert(DocumentNode::new("параграф", String::new(), text.to_string(), None, start + i + 1, start + i + 1, 1, "параграф").with_amendments(amendments, repealed));
+        }
+    }
+
+    #[test]
+    fn test_align()
+    {
+        let pairs = super::align(&["a", "b", "c", "d"], &["a", "x", "c", "d", "e"]);
+        assert_eq!(pairs, vec![(Some(0), Some(0)), (Some(1), Some(1)), (Some(2), Some(2)), (Some(3), Some(3)), (None, Some(4))]);
+        let pairs = super::align(&["a", "b", "c"], &["a", "c"]);
+        assert_eq!(pairs, vec![(Some(0), Some(0)), (Some(1), None), (Some(2), Some(1))]);
+    }
+
+    #[test]
+    fn test_redaction_report()
+    {
+        let mut previous: DocumentNodes<String> = DocumentNodes::default();
+        article(&mut previous, 1, "Статья 1", &[("1. Первая часть.", None), ("2. Вторая часть.", None), ("3. Третья часть.", None)]);
+        let mut current: DocumentNodes<String> = DocumentNodes::default();
+        article(&mut current, 1, "Статья 1", &[
+            ("1. Первая часть в новой редакции.", Some("(В редакции Федерального закона от 26.07.2019 № 232-ФЗ)")),
+            ("2. Утратила силу.", Some("(Утратила силу - Федеральный закон от 26.07.2019 № 232-ФЗ)")),
+            ("3. Третья часть.", None),
+            ("4. Четвертая часть.", Some("(Дополнение частью - Федеральный закон от 26.07.2019 № 232-ФЗ)"))]);
+        let redaction = ExtendedRedaction
+        {
+            id: 11,
+            date: Date::new_date(1, 9, 2019),
+            state_id: 1,
+            state: DocumentState::InForceWithChanges,
+            elements: 0,
+            redaction_type: RedactionType::Scheduled,
+            caption: String::new(),
+            status: RedactionStatus::Actual,
+            flag: 0,
+            is_actual: true,
+            source_number: Some("232-ФЗ".to_owned()),
+            source_date: Some(Date::new_date(26, 7, 2019)),
+            effective_from: Some(Date::new_date(1, 9, 2019)),
+            effective_to: None
+        };
+        let report = RedactionReport::compare(&previous, &current, redaction);
+        let kinds: Vec<ChangeKind> = report.changes.iter().map(|c| c.kind).collect();
+        assert_eq!(kinds, vec![ChangeKind::Modified, ChangeKind::Repealed, ChangeKind::Added]);
+        assert!(report.changes.iter().all(|c| c.article.as_deref() == Some("Статья 1") && c.acts.len() == 1));
+        assert_eq!(report.changes[0].before.as_deref(), Some("1. Первая часть."));
+        let md = report.to_markdown();
+        assert!(md.contains("### Статья 1"));
+        assert!(md.contains("> 1. Первая часть в новой редакции."));
+        assert!(serde_json::to_string(&report).is_ok());
+    }
+}
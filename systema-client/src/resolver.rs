@@ -0,0 +1,139 @@
+use std::sync::LazyLock;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use utilites::Date;
+use crate::{actual_redactions_client::{ActualRedactionsClient, DocumentResponse, RedactionTtl}, config::ClientConfig, error::{Error, Result}, ibpi_client::SystemaIpsApi, source::{self, LegalSource}};
+
+static HASH_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[0-9a-f]{64}$").unwrap());
+static EO_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\d{16}$").unwrap());
+static URL_HASH_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:hash|gohash)=(?<hash>[0-9a-f]{64})").unwrap());
+static URL_EO_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:pnum=|/)(?<eo>\d{16})(?:\D|$)").unwrap());
+static URL_ND_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\bnd=(?<nd>\d+)").unwrap());
+static NUMBER_DATE_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(№\s*)?(?<number>[\dА-Яа-яA-Za-z\-/]+)\s+от\s+(?<date>\d{2}[.]\d{2}[.]\d{4})").unwrap());
+static DATE_NUMBER_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(от\s+)?(?<date>\d{2}[.]\d{2}[.]\d{4})\s+(года\s+)?(№\s*)?(?<number>[\dА-Яа-яA-Za-z\-/]+)$").unwrap());
+
+///Ссылка на документ по любому из его идентификаторов
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum DocumentRef
+{
+    ///дата подписания и номер `273-ФЗ`
+    NumberAndDate(Date, String),
+    ///хеш документа в api актуальных редакций, такие же хеши в ссылках `cmdprm="gohash=..."`
+    Hash(String),
+    ///номер электронного опубликования `0001202405110002`
+    EoNumber(String),
+    ///идентификатор документа в ИПС `nd=102162745`
+    IpsNd(String),
+    ///ссылка на документ на одном из порталов pravo.gov.ru
+    PublicationUrl(String)
+}
+
+impl DocumentRef
+{
+    ///Разбор того что вставил пользователь: ссылка, хеш, номер опубликования, `nd=...`, `273-ФЗ от 29.12.2012`
+    pub fn parse(value: &str) -> Option<Self>
+    {
+        let value = value.trim();
+        if value.starts_with("http://") || value.starts_with("https://") || value.contains("pravo.gov.ru")
+        {
+            Some(Self::PublicationUrl(value.to_owned()))
+        }
+        else if HASH_RX.is_match(value)
+        {
+            Some(Self::Hash(value.to_owned()))
+        }
+        else if EO_RX.is_match(value)
+        {
+            Some(Self::EoNumber(value.to_owned()))
+        }
+        else if let Some(nd) = URL_ND_RX.captures(value).and_then(|c| c.name("nd"))
+        {
+            Some(Self::IpsNd(nd.as_str().to_owned()))
+        }
+        else
+        {
+            let cpt = NUMBER_DATE_RX.captures(value).or_else(|| DATE_NUMBER_RX.captures(value))?;
+            let date = Date::parse(cpt.name("date")?.as_str())?;
+            Some(Self::NumberAndDate(date, cpt.name("number")?.as_str().to_owned()))
+        }
+    }
+    ///Идентификатор из ссылки на портал:
+    /// `http://actual.pravo.gov.ru/list.html#hash=...`, `http://publication.pravo.gov.ru/Document/View/0001202405110002`,
+    /// `http://pravo.gov.ru/proxy/ips/?docbody=&nd=102162745`
+    pub fn from_url(url: &str) -> Option<Self>
+    {
+        if let Some(hash) = URL_HASH_RX.captures(url).and_then(|c| c.name("hash"))
+        {
+            Some(Self::Hash(hash.as_str().to_owned()))
+        }
+        else if let Some(eo) = URL_EO_RX.captures(url).and_then(|c| c.name("eo"))
+        {
+            Some(Self::EoNumber(eo.as_str().to_owned()))
+        }
+        else
+        {
+            URL_ND_RX.captures(url).and_then(|c| c.name("nd")).map(|nd| Self::IpsNd(nd.as_str().to_owned()))
+        }
+    }
+}
+
+///Документ по любой ссылке (`DocumentRef`): документы из api актуальных редакций, `nd` - из ИПС
+#[derive(Default)]
+pub struct DocumentResolver
+{
+    config: ClientConfig
+}
+impl DocumentResolver
+{
+    pub fn new(config: ClientConfig) -> Self
+    {
+        Self { config }
+    }
+}
+impl LegalSource for DocumentResolver
+{
+    type Id = DocumentRef;
+    async fn fetch(&self, id: &Self::Id) -> Result<DocumentResponse>
+    {
+        let client = ActualRedactionsClient::new(self.config.clone());
+        match id
+        {
+            DocumentRef::NumberAndDate(sign_date, number) => client.get_document(sign_date.clone(), number).await,
+            DocumentRef::Hash(hash) => client.get_document_by_redactions(&client.get_redactions_by_hash(hash, RedactionTtl::Actual).await?, None).await,
+            DocumentRef::EoNumber(eo) => client.get_document_by_redactions(&client.get_redactions_by_eo_number(eo, RedactionTtl::Actual).await?, Some(eo)).await,
+            DocumentRef::IpsNd(nd) => source::ips_document(&SystemaIpsApi::by_nd(&self.config, nd), None, None).await,
+            //ссылка на портал заменяется идентификатором из нее, ссылкой он уже не будет
+            DocumentRef::PublicationUrl(url) => match DocumentRef::from_url(url)
+            {
+                Some(resolved) => Box::pin(self.fetch(&resolved)).await,
+                None => Err(Error::ContentError(["Не удалось определить документ по ссылке ", url].concat()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use utilites::Date;
+    use super::DocumentRef;
+
+    #[test]
+    fn test_parse_document_ref()
+    {
+        let hash = "cc4a7f7b594d929a90706d741ac9cf532ace317526834f447188dba16999c1aa";
+        assert_eq!(DocumentRef::parse(hash), Some(DocumentRef::Hash(hash.to_owned())));
+        assert_eq!(DocumentRef::parse(" 0001202405110002 "), Some(DocumentRef::EoNumber("0001202405110002".to_owned())));
+        assert_eq!(DocumentRef::parse("nd=102162745"), Some(DocumentRef::IpsNd("102162745".to_owned())));
+        assert_eq!(DocumentRef::parse("273-ФЗ от 29.12.2012"), Some(DocumentRef::NumberAndDate(Date::new_date(29, 12, 2012), "273-ФЗ".to_owned())));
+        assert_eq!(DocumentRef::parse("от 29.12.2012 № 273-ФЗ"), Some(DocumentRef::NumberAndDate(Date::new_date(29, 12, 2012), "273-ФЗ".to_owned())));
+        assert!(DocumentRef::parse("закон об образовании").is_none());
+
+        let url = "http://publication.pravo.gov.ru/Document/View/0001202405110002";
+        assert_eq!(DocumentRef::parse(url), Some(DocumentRef::PublicationUrl(url.to_owned())));
+        assert_eq!(DocumentRef::from_url(url), Some(DocumentRef::EoNumber("0001202405110002".to_owned())));
+        assert_eq!(DocumentRef::from_url(&["http://actual.pravo.gov.ru/list.html#hash=", hash].concat()), Some(DocumentRef::Hash(hash.to_owned())));
+        assert_eq!(DocumentRef::from_url("http://pravo.gov.ru/proxy/ips/?docbody=&link_id=0&nd=102162745&intelsearch="), Some(DocumentRef::IpsNd("102162745".to_owned())));
+        assert!(DocumentRef::from_url("http://pravo.gov.ru/").is_none());
+    }
+}
@@ -39,6 +39,7 @@ struct IdParams
     param: u32,
 }
 
+#[derive(Debug, Clone, Copy)]
 pub enum DocumentKind
 {
     Fz,
@@ -46,7 +47,7 @@ pub enum DocumentKind
 }
 impl DocumentKind
 {
-    pub fn as_id_param(&self) -> IdParams
+    pub(crate) fn as_id_param(&self) -> IdParams
     {
         match self 
         {
@@ -56,6 +57,36 @@ impl DocumentKind
     }
 }
 
+///Где искать слова текстового запроса
+#[derive(Debug, Clone, Copy)]
+pub enum TextScope
+{
+    ///по всему тексту документа
+    FullText,
+    ///только по названию документа
+    Title
+}
+impl TextScope
+{
+    ///Номера атрибутов текста и названия документации api не имеют, подобраны как у поиска по номеру (`AttrId` 6, `AttrMode` 8)  
+    /// и проверяются живыми запросами `test_text_search_request` и `test_title_search_request` в `actual_redactions_client`:
+    /// по названию "Об образовании в Российской Федерации" должен находиться 273-ФЗ, по тексту "налоговая тайна" - Налоговый кодекс  
+    /// отправляемый `q` зафиксирован в `test_text_search_q`
+    fn attr_id(&self) -> u32
+    {
+        match self
+        {
+            TextScope::FullText => 0,
+            TextScope::Title => 1,
+        }
+    }
+}
+///сортировка по дате подписания, от новых к старым
+const SORT_BY_DATE: &str = "-date";
+///сортировка по релевантности текстовому запросу, тоже без документации, проверяется теми же тестами что и `TextScope::attr_id`:
+/// искомый документ должен быть среди первых результатов
+const SORT_BY_RELEVANCE: &str = "-rank";
+
 impl SearchAttributes
 {
     ///на вход принимается вектор из двух атрибутов  
@@ -112,18 +143,50 @@ impl SearchAttributes
                     words: Some(vec![n.to_owned()])
                 });
         }
+        s.push(Self::sort_attribute(pages, SORT_BY_DATE));
+        s
+    }
+    ///Атрибуты полнотекстового поиска, к фильтру по дате и виду документа добавляется атрибут `Words` с текстом запроса  
+    /// результаты сортируются по релевантности
+    /// ```json
+    /// [
+    ///     {"AttrId":5,"AttrMode":0,"DateTo":"20251222"},
+    ///     {"AttrId":4,"AttrMode":1,"IDParams":[{"Id":108,"Param":0},{"Id":107,"Param":0}]},
+    ///     {"AttrId":0,"AttrMode":8,"Words":["налоговая тайна"]},
+    ///     {"AttrId":999,"AttrMode":1,"Words":[50,"-rank","20220701",0,1]}
+    /// ]
+    /// ```
+    pub fn get_text_search_attributes_vec(query: &str, scope: TextScope, date_from: Option<Date>, date_to: Date, kinds: &[DocumentKind], pages: u32)-> Vec<Self>
+    {
+        let mut s = Self::get_search_attributes_vec(date_from, date_to, kinds, pages, None);
+        //последним всегда идет атрибут сортировки
+        s.pop();
         s.push
         (Self
-            { 
-                attr_id: 999,
-                attr_mode: 1,
+            {
+                attr_id: scope.attr_id(),
+                attr_mode: 8,
                 date_from: None,
                 date_to: None,
                 id_params: None,
-                words: Some(vec![pages.to_string(), "-date".to_owned(), "20220701".to_owned(), "0".to_owned(), "1".to_owned()])
+                words: Some(vec![query.to_owned()])
             });
+        s.push(Self::sort_attribute(pages, SORT_BY_RELEVANCE));
         s
     }
+    ///Атрибут с количеством документов на странице и порядком сортировки
+    fn sort_attribute(pages: u32, sort: &str) -> Self
+    {
+        Self
+        {
+            attr_id: 999,
+            attr_mode: 1,
+            date_from: None,
+            date_to: None,
+            id_params: None,
+            words: Some(vec![pages.to_string(), sort.to_owned(), "20220701".to_owned(), "0".to_owned(), "1".to_owned()])
+        }
+    }
     /// http://actual.pravo.gov.ru:8000/api/ebpi/attrsearch/?q=[{"AttrId":5,"AttrMode":0,"DateFrom":"20240101","DateTo":"20240620"},{"AttrId":999,"AttrMode":1,"Words":[50,"-date","20220701",0,1]}]
     /// только кавычки в эскейпе -> %22
     pub fn get_search_uri(date_from: Option<Date>, date_to: Date, kinds: &[DocumentKind], pages: u32, number: Option<&str>)-> String
@@ -173,4 +236,22 @@ where
         ser.end()
     }
    
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests
+{
+    use utilites::Date;
+    use super::{DocumentKind, SearchAttributes, TextScope};
+
+    ///`q` полнотекстового поиска и поиска по названию, в таком виде он уходит в `attrsearch`
+    #[test]
+    fn test_text_search_q()
+    {
+        let q = |scope: TextScope| serde_json::to_string(&SearchAttributes::get_text_search_attributes_vec("налоговая тайна", scope, Some(Date::new_date(1, 1, 2024)), Date::new_date(22, 12, 2025), &[DocumentKind::Fz, DocumentKind::Fkz], 50)).unwrap();
+        assert_eq!(q(TextScope::FullText), r#"[{"AttrId":5,"AttrMode":0,"DateFrom":"20240101","DateTo":"20251222"},{"AttrId":4,"AttrMode":1,"IDParams":[{"Id":108,"Param":0},{"Id":107,"Param":0}]},{"AttrId":0,"AttrMode":8,"Words":["налоговая тайна"]},{"AttrId":999,"AttrMode":1,"Words":[50,"-rank","20220701",0,1]}]"#);
+        assert_eq!(q(TextScope::Title), r#"[{"AttrId":5,"AttrMode":0,"DateFrom":"20240101","DateTo":"20251222"},{"AttrId":4,"AttrMode":1,"IDParams":[{"Id":108,"Param":0},{"Id":107,"Param":0}]},{"AttrId":1,"AttrMode":8,"Words":["налоговая тайна"]},{"AttrId":999,"AttrMode":1,"Words":[50,"-rank","20220701",0,1]}]"#);
+        //поиск по номеру для сравнения, сортировка по дате
+        let q = serde_json::to_string(&SearchAttributes::get_search_attributes_vec(None, Date::new_date(22, 12, 2025), &[DocumentKind::Fz], 50, Some("273-ФЗ"))).unwrap();
+        assert_eq!(q, r#"[{"AttrId":5,"AttrMode":0,"DateTo":"20251222"},{"AttrId":4,"AttrMode":1,"IDParams":[{"Id":108,"Param":0}]},{"AttrId":6,"AttrMode":8,"Words":["273-ФЗ"]},{"AttrId":999,"AttrMode":1,"Words":[50,"-date","20220701",0,1]}]"#);
+    }
+}
@@ -0,0 +1,280 @@
+use std::{fmt::Debug, future::Future, path::{Path, PathBuf}, sync::LazyLock};
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use tracing::info;
+use utilites::Date;
+use crate::{charset, actual_redactions_client::{ActualRedactionsClient, DocumentResponse}, builder, config::ClientConfig, converter::Converter, document::DocumentNodes, error::{Error, Result}, ibpi_client::{DocumentKindSearchParams, SystemaIpsApi}, metadata::DocumentMetadata, models::{Contents, DocumentState}};
+
+static P_TAG_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<p(?<rest>\s[^>]*)?>").unwrap());
+
+///Источник правовых документов, из любого источника документ проходит через одно и то же построение дерева узлов
+/// так в общий конвейер (чанки, эмбеддинги, хранилище) попадают и ЛНА и выгрузки судебной практики
+pub trait LegalSource
+{
+    ///идентификатор документа в источнике
+    type Id;
+    ///исходный html документа с содержанием и реквизитами
+    fn fetch(&self, id: &Self::Id) -> impl Future<Output = Result<DocumentResponse>>;
+    ///Дерево узлов документа
+    fn get_document<CONV, CONT>(&self, id: &Self::Id, converter: &CONV) -> impl Future<Output = Result<DocumentNodes<CONT>>>
+    where   CONT: ToString + Debug,
+            CONV: Converter<CONT>
+    {
+        async move
+        {
+            let document = self.fetch(id).await?;
+            builder::build_nodes(document, converter)
+        }
+    }
+}
+
+///Актуальные редакции с actual.pravo.gov.ru (api ebpi), документ ищется по дате подписания и номеру
+#[derive(Default)]
+pub struct EbpiSource
+{
+    client: ActualRedactionsClient
+}
+impl EbpiSource
+{
+    pub fn new(config: ClientConfig) -> Self
+    {
+        Self { client: ActualRedactionsClient::new(config) }
+    }
+}
+impl LegalSource for EbpiSource
+{
+    type Id = (Date, String);
+    fn fetch(&self, id: &Self::Id) -> impl Future<Output = Result<DocumentResponse>>
+    {
+        self.client.get_document(id.0.clone(), &id.1)
+    }
+}
+
+///ИПС pravo.gov.ru, содержания у документов нет поэтому все параграфы документа идут подряд
+pub struct IpsSource
+{
+    pub kinds: Vec<DocumentKindSearchParams>,
+    pub config: ClientConfig
+}
+impl Default for IpsSource
+{
+    fn default() -> Self
+    {
+        Self { kinds: vec![DocumentKindSearchParams::Fz, DocumentKindSearchParams::Fkz], config: ClientConfig::default() }
+    }
+}
+impl LegalSource for IpsSource
+{
+    type Id = (Date, String);
+    async fn fetch(&self, id: &Self::Id) -> Result<DocumentResponse>
+    {
+        let (sign_date, number) = id;
+        let api = SystemaIpsApi::search(&self.config, &self.kinds, number, sign_date.clone()).await?;
+        ips_document(&api, Some(number), Some(sign_date)).await
+    }
+}
+
+///Документ со страницы ИПС, реквизиты которых не передали берутся из шапки и подписи документа
+pub(crate) async fn ips_document(api: &SystemaIpsApi, number: Option<&str>, sign_date: Option<&Date>) -> Result<DocumentResponse>
+{
+    let html = number_paragraphs(&api.get_document_html().await?.html());
+    let metadata = DocumentMetadata::from_html(&html);
+    let nd = api.current_uri().split("nd=").nth(1).and_then(|nd| nd.split('&').next()).unwrap_or_default().to_owned();
+    let number = number.map(|n| n.to_owned()).or(metadata.number).unwrap_or_default();
+    let sign_date = sign_date.cloned().or(metadata.sign_date)
+        .ok_or(Error::ContentError(["Не удалось определить дату подписания документа nd=", &nd].concat()))?;
+    Ok(DocumentResponse
+    {
+        html,
+        contents: Contents::default(),
+        name: metadata.title.unwrap_or(number.clone()),
+        number,
+        sign_date,
+        publication_url: api.current_uri().to_owned(),
+        state: DocumentState::Unknown,
+        hash: ["ips-", &nd].concat(),
+        redaction_id: 0
+    })
+}
+
+///Реквизиты документа из файла `<id>.json` рядом с `<id>.html`
+/// все поля необязательные, чего нет берется из шапки и подписи документа
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct LocalMetadata
+{
+    pub name: Option<String>,
+    pub number: Option<String>,
+    ///`dd.mm.yyyy`
+    pub sign_date: Option<String>,
+    pub publication_url: Option<String>,
+    pub state: Option<DocumentState>,
+    pub redaction_id: u32
+}
+
+impl DocumentResponse
+{
+    ///Документ из сохраненного html (`redtext` или страница ИПС), дальше он строится так же как полученный из api
+    /// `contents` - содержание в формате `getcontent`, без него все параграфы идут подряд
+    pub fn from_html(id: &str, html: &str, meta: LocalMetadata, contents: Option<Contents>) -> Result<Self>
+    {
+        let html = ActualRedactionsClient::clear_document_html(&number_paragraphs(html))?;
+        let metadata = DocumentMetadata::from_html(&html);
+        let sign_date = meta.sign_date.as_deref().and_then(Date::parse)
+            .or(metadata.sign_date)
+            .ok_or(Error::ContentError(["Не удалось определить дату подписания документа ", id].concat()))?;
+        Ok(Self
+        {
+            html,
+            contents: contents.unwrap_or_default(),
+            name: meta.name.or(metadata.title).unwrap_or(id.to_owned()),
+            number: meta.number.or(metadata.number).unwrap_or_default(),
+            sign_date,
+            publication_url: meta.publication_url.unwrap_or_default(),
+            state: meta.state.unwrap_or_default(),
+            hash: ["local-", id].concat(),
+            redaction_id: meta.redaction_id
+        })
+    }
+}
+
+///Сохраненный html файл `<имя>.html`, рядом могут лежать реквизиты `<имя>.json` и содержание `<имя>.contents.json`
+pub struct LocalFileSource;
+impl LegalSource for LocalFileSource
+{
+    type Id = PathBuf;
+    async fn fetch(&self, id: &Self::Id) -> Result<DocumentResponse>
+    {
+        //сохраненные со страниц ИПС файлы обычно в windows-1251
+        let html = charset::decode_html(&tokio::fs::read(id).await?, None).text;
+        let meta: Option<LocalMetadata> = read_sidecar(&id.with_extension("json")).await?;
+        if meta.is_none()
+        {
+            info!("для {} нет файла с реквизитами, реквизиты берем из текста документа", id.display());
+        }
+        let contents: Option<Contents> = read_sidecar(&id.with_extension("contents.json")).await?;
+        let name = id.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        let mut meta = meta.unwrap_or_default();
+        meta.publication_url.get_or_insert(id.display().to_string());
+        DocumentResponse::from_html(&name, &html, meta, contents)
+    }
+}
+
+async fn read_sidecar<T: serde::de::DeserializeOwned>(path: &Path) -> Result<Option<T>>
+{
+    if tokio::fs::try_exists(path).await?
+    {
+        Ok(Some(serde_json::from_str(&tokio::fs::read_to_string(path).await?)?))
+    }
+    else
+    {
+        Ok(None)
+    }
+}
+
+///Каталог с сохраненными документами, каждый документ как в `LocalFileSource`
+pub struct LocalDirectorySource
+{
+    dir: PathBuf
+}
+impl LocalDirectorySource
+{
+    pub fn new(dir: impl Into<PathBuf>) -> Self
+    {
+        Self { dir: dir.into() }
+    }
+    ///Идентификаторы всех документов в каталоге (имена html файлов без расширения)
+    pub fn ids(&self) -> Result<Vec<String>>
+    {
+        let mut ids = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)?
+        {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "html") && let Some(stem) = path.file_stem()
+            {
+                ids.push(stem.to_string_lossy().into_owned());
+            }
+        }
+        ids.sort();
+        Ok(ids)
+    }
+}
+impl LegalSource for LocalDirectorySource
+{
+    type Id = String;
+    fn fetch(&self, id: &Self::Id) -> impl Future<Output = Result<DocumentResponse>>
+    {
+        let path = self.dir.join([id, ".html"].concat());
+        async move { LocalFileSource.fetch(&path).await }
+    }
+}
+
+///Нумерация параграфов `id="p<n>"` для html без разметки параграфов (ИПС, сторонние выгрузки)
+/// если в документе уже есть `id="p..."` html не меняется
+pub(crate) fn number_paragraphs(html: &str) -> String
+{
+    if html.contains("id=\"p")
+    {
+        return html.to_owned();
+    }
+    let mut counter = 0;
+    P_TAG_RX.replace_all(html, |cpt: &Captures|
+    {
+        counter += 1;
+        ["<p id=\"p", &counter.to_string(), "\"", cpt.name("rest").map(|r| r.as_str()).unwrap_or_default(), ">"].concat()
+    }).into_owned()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::{converter, logger};
+    use crate::actual_redactions_client::DocumentResponse;
+    use super::{LegalSource, LocalDirectorySource, LocalMetadata};
+
+    struct NotConvert;
+    impl converter::Converter<String> for NotConvert
+    {
+        fn convert(&self, html: String) -> String
+        {
+            html
+        }
+    }
+
+    #[test]
+    fn test_number_paragraphs()
+    {
+        assert_eq!(super::number_paragraphs(r#"<p>один</p><p class="T">два</p><pre>x</pre>"#), r#"<p id="p1">один</p><p id="p2" class="T">два</p><pre>x</pre>"#);
+        let numbered = r#"<p id="p5">один</p><p>два</p>"#;
+        assert_eq!(super::number_paragraphs(numbered), numbered);
+    }
+
+    #[test]
+    fn test_saved_redtext()
+    {
+        let document = DocumentResponse::from_html("test_doc1", include_str!("../../rag/src/test_doc1.html"), LocalMetadata::default(), None).unwrap();
+        assert_eq!(document.number, "273-ФЗ");
+        assert_eq!(document.name, "Об образовании в Российской Федерации");
+        //неразрывный пробел остается символом U+00A0, как и в html полученном из api
+        assert!(!document.html.contains("&nbsp;") && document.html.contains('\u{a0}'));
+    }
+
+    #[tokio::test]
+    async fn test_local_directory()
+    {
+        logger::init();
+        let dir = std::env::temp_dir().join(["systema_local_source_test_", &std::process::id().to_string()].concat());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("doc.html"), r#"<p class="T">ПОЛОЖЕНИЕ</p><p class="Z">О порядке работы</p><p>1. Первый пункт.</p><p>2. Второй пункт.</p>"#).unwrap();
+        std::fs::write(dir.join("doc.json"), r#"{"number":"12-п","sign_date":"01.02.2024"}"#).unwrap();
+        std::fs::write(dir.join("doc.contents.json"), r#"{"data":[{"id":"a1","np":"p3","npe":"p4","caption":"$Пункты","unit":"раздел","lvl":0}]}"#).unwrap();
+        let source = LocalDirectorySource::new(&dir);
+        assert_eq!(source.ids().unwrap(), vec!["doc".to_owned()]);
+        let nodes = source.get_document(&"doc".to_owned(), &NotConvert).await.unwrap();
+        assert_eq!(nodes.number(), "12-п");
+        assert_eq!(nodes.title(), "О порядке работы");
+        assert_eq!(nodes.node_count(), 2);
+        assert_eq!(nodes.get_children(0).len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,418 @@
+use std::cell::RefCell;
+use html5ever::{tendril::StrTendril, tokenizer::{BufferQueue, Tag, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts, states::RawKind}};
+use crate::annotations::Amendment;
+
+const VOID_ELEMENTS: [&str; 13] = ["area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr"];
+///блочные элементы которые закрывают незакрытый `<p>` (в старых страницах ИПС `</p>` часто нет)
+const BLOCK_ELEMENTS: [&str; 16] = ["p", "div", "table", "ul", "ol", "dl", "pre", "blockquote", "section", "hr", "h1", "h2", "h3", "h4", "h5", "h6"];
+
+///Параграф документа из потокового разбора html
+/// служебная разметка (`span.mark`, `p.F`, `p.A`, `label`, `&nbsp;`, классы `ed`/`edx`) убирается так же как в `clear_document_html`
+#[derive(Debug, Clone, Default)]
+pub struct Paragraph
+{
+    ///номер из `id="p<n>"`
+    pub id: Option<usize>,
+    pub class: String,
+    ///html параграфа вместе с аннотациями
+    pub html: String,
+    ///html параграфа без аннотаций `span.markx`
+    pub html_without_amendments: String,
+    ///текст без аннотаций
+    pub text: String,
+    ///хеши документов на которые ссылается текст параграфа (`span[cmdprm]` вне аннотаций)
+    pub links: Vec<String>,
+    pub amendments: Vec<Amendment>
+}
+impl Paragraph
+{
+    pub fn has_class(&self, class: &str) -> bool
+    {
+        self.class.split_whitespace().any(|c| c.eq_ignore_ascii_case(class))
+    }
+}
+
+///Открытый элемент внутри параграфа
+struct Open
+{
+    name: String,
+    markx: bool,
+    del: bool
+}
+
+///Текущая аннотация `span.markx`
+#[derive(Default)]
+struct Markx
+{
+    text: String,
+    hashes: Vec<String>
+}
+
+struct State<F: FnMut(Paragraph)>
+{
+    on_paragraph: F,
+    current: Option<Paragraph>,
+    stack: Vec<Open>,
+    markx: Option<Markx>,
+    ///пропускаемый элемент и вложенность элементов с таким же именем
+    skip: Option<(String, usize)>
+}
+
+impl<F: FnMut(Paragraph)> State<F>
+{
+    fn in_markx(&self) -> bool
+    {
+        self.stack.iter().any(|o| o.markx)
+    }
+    fn in_del(&self) -> bool
+    {
+        self.stack.iter().any(|o| o.del)
+    }
+    ///html пишется в оба варианта параграфа, вне аннотаций
+    fn write(&mut self, html: &str)
+    {
+        let in_markx = self.in_markx();
+        if let Some(p) = self.current.as_mut()
+        {
+            p.html.push_str(html);
+            if !in_markx
+            {
+                p.html_without_amendments.push_str(html);
+            }
+        }
+    }
+    fn close_paragraph(&mut self)
+    {
+        while let Some(open) = self.stack.pop()
+        {
+            self.close_element(open);
+        }
+        if let Some(mut p) = self.current.take()
+        {
+            p.html.push_str("</p>");
+            p.html_without_amendments.push_str("</p>");
+            (self.on_paragraph)(p);
+        }
+    }
+    fn close_element(&mut self, open: Open)
+    {
+        let end = ["</", &open.name, ">"].concat();
+        if let Some(p) = self.current.as_mut()
+        {
+            p.html.push_str(&end);
+            if !open.markx && !self.in_markx()
+            {
+                p.html_without_amendments.push_str(&end);
+            }
+        }
+        if open.markx && let Some(markx) = self.markx.take() && let Some(p) = self.current.as_mut()
+        {
+            p.amendments.push(Amendment::parse(&markx.text, &markx.hashes));
+        }
+    }
+    fn start_tag(&mut self, tag: Tag)
+    {
+        let name = tag.name.to_string();
+        let attr = |key: &str| tag.attrs.iter().find(|a| &*a.name.local == key).map(|a| a.value.to_string());
+        let class = attr("class").filter(|c| c != "ed" && c != "edx").unwrap_or_default();
+        let has_class = |c: &str| class.split_whitespace().any(|cl| cl.eq_ignore_ascii_case(c));
+        if let Some((skip_name, depth)) = self.skip.as_mut()
+        {
+            //у незакрытого пропускаемого `<p>` конец там где начинается следующий
+            if !(skip_name.as_str() == "p" && name == "p")
+            {
+                if *skip_name == name && !tag.self_closing
+                {
+                    *depth += 1;
+                }
+                return;
+            }
+            self.skip = None;
+        }
+        if self.current.is_some() && BLOCK_ELEMENTS.contains(&name.as_str())
+        {
+            self.close_paragraph();
+        }
+        let skipped = match name.as_str()
+        {
+            "label" => true,
+            "span" => has_class("mark"),
+            "p" => has_class("F") || has_class("A") || has_class("mark") || has_class("markx"),
+            _ => false
+        };
+        if skipped
+        {
+            if !tag.self_closing && !VOID_ELEMENTS.contains(&name.as_str())
+            {
+                self.skip = Some((name, 1));
+            }
+            return;
+        }
+        if name == "p"
+        {
+            let id = attr("id").and_then(|id| id.strip_prefix("p").and_then(|id| id.parse().ok()));
+            self.current = Some(Paragraph { id, class: class.clone(), ..Default::default() });
+        }
+        if self.current.is_none()
+        {
+            return;
+        }
+        if name == "span" && let Some(cmd) = attr("cmdprm")
+        {
+            let hash = cmd.split_whitespace().next().and_then(|h| h.strip_prefix("gohash=")).map(|h| h.to_owned());
+            //ссылки из аннотаций это изменившие документ акты, они остаются только в `Amendment::acts`
+            if let Some(hash) = hash
+            {
+                if let Some(markx) = self.markx.as_mut()
+                {
+                    markx.hashes.push(hash);
+                }
+                else if let Some(p) = self.current.as_mut()
+                {
+                    p.links.push(hash);
+                }
+            }
+        }
+        let mut html = ["<", &name].concat();
+        for a in &tag.attrs
+        {
+            let value = a.value.to_string();
+            if &*a.name.local == "class" && (value == "ed" || value == "edx")
+            {
+                continue;
+            }
+            html.push_str(&[" ", &*a.name.local, "=\"", &escape(&value, true), "\""].concat());
+        }
+        html.push('>');
+        let markx = name == "span" && has_class("markx");
+        if markx
+        {
+            //открывающий тег аннотации в html без аннотаций не пишем
+            self.markx = Some(Markx::default());
+            if let Some(p) = self.current.as_mut()
+            {
+                p.html.push_str(&html);
+            }
+        }
+        else
+        {
+            self.write(&html);
+        }
+        if name == "p" || tag.self_closing || VOID_ELEMENTS.contains(&name.as_str())
+        {
+            return;
+        }
+        self.stack.push(Open { del: name == "del", markx, name });
+    }
+    fn end_tag(&mut self, tag: Tag)
+    {
+        let name = tag.name.to_string();
+        if let Some((skip_name, depth)) = self.skip.as_mut()
+        {
+            if *skip_name == name
+            {
+                *depth -= 1;
+                if *depth == 0
+                {
+                    self.skip = None;
+                }
+            }
+            return;
+        }
+        if self.current.is_none()
+        {
+            return;
+        }
+        if let Some(pos) = self.stack.iter().rposition(|o| o.name == name)
+        {
+            while self.stack.len() > pos
+            {
+                let open = self.stack.pop().unwrap();
+                self.close_element(open);
+            }
+        }
+        else if BLOCK_ELEMENTS.contains(&name.as_str()) || name == "body" || name == "td"
+        {
+            self.close_paragraph();
+        }
+    }
+    fn characters(&mut self, text: &str)
+    {
+        if self.skip.is_some() || self.current.is_none()
+        {
+            return;
+        }
+        let text = text.replace('\u{a0}', " ");
+        self.write(&escape(&text, false));
+        let in_del = self.in_del();
+        if let Some(markx) = self.markx.as_mut()
+        {
+            if !in_del
+            {
+                markx.text.push_str(&text);
+            }
+        }
+        else if let Some(p) = self.current.as_mut()
+        {
+            p.text.push_str(&text);
+        }
+    }
+}
+
+struct Sink<F: FnMut(Paragraph)>
+{
+    state: RefCell<State<F>>
+}
+impl<F: FnMut(Paragraph)> TokenSink for Sink<F>
+{
+    type Handle = ();
+    fn process_token(&self, token: Token, _line_number: u64) -> TokenSinkResult<()>
+    {
+        let mut state = self.state.borrow_mut();
+        match token
+        {
+            Token::TagToken(tag) => match tag.kind
+            {
+                TagKind::StartTag =>
+                {
+                    let raw = match &*tag.name
+                    {
+                        "script" => Some(RawKind::ScriptData),
+                        "style" => Some(RawKind::Rawtext),
+                        _ => None
+                    };
+                    state.start_tag(tag);
+                    if let Some(raw) = raw
+                    {
+                        return TokenSinkResult::RawData(raw);
+                    }
+                }
+                TagKind::EndTag => state.end_tag(tag)
+            },
+            Token::CharacterTokens(text) => state.characters(&text),
+            Token::EOFToken => state.close_paragraph(),
+            _ => ()
+        }
+        TokenSinkResult::Continue
+    }
+}
+
+pub(crate) fn escape(text: &str, attribute: bool) -> String
+{
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars()
+    {
+        match c
+        {
+            '&' => escaped.push_str("&amp;"),
+            '"' if attribute => escaped.push_str("&quot;"),
+            '<' if !attribute => escaped.push_str("&lt;"),
+            '>' if !attribute => escaped.push_str("&gt;"),
+            c => escaped.push(c)
+        }
+    }
+    escaped
+}
+
+///Потоковый разбор параграфов документа за один проход токенизатора, без построения DOM
+/// для кодексов это в разы меньше памяти чем `Html::parse_document` + `clear_document_html` + повторный разбор
+pub fn stream_paragraphs<F: FnMut(Paragraph)>(html: &str, on_paragraph: F)
+{
+    let sink = Sink
+    {
+        state: RefCell::new(State { on_paragraph, current: None, stack: Vec::new(), markx: None, skip: None })
+    };
+    let tokenizer = Tokenizer::new(sink, TokenizerOpts::default());
+    let input = BufferQueue::default();
+    input.push_back(StrTendril::from_slice(html));
+    let _ = tokenizer.feed(&input);
+    tokenizer.end();
+}
+
+///Все параграфы документа, см. `stream_paragraphs`
+pub fn paragraphs(html: &str) -> Vec<Paragraph>
+{
+    let mut paragraphs = Vec::new();
+    stream_paragraphs(html, |p| paragraphs.push(p));
+    paragraphs
+}
+
+#[cfg(test)]
+mod tests
+{
+    use scraper::{ElementRef, Html, Selector};
+    use crate::{actual_redactions_client::ActualRedactionsClient, annotations::Amendment};
+
+    //разбор параграфа через DOM как было до потокового разбора, для сравнения результатов
+    fn dom_amendments(p: &ElementRef) -> Vec<Amendment>
+    {
+        let markx_selector = Selector::parse("span.markx").unwrap();
+        let links_selector = Selector::parse("span[cmdprm]").unwrap();
+        p.select(&markx_selector).map(|m|
+        {
+            let hashes: Vec<String> = m.select(&links_selector)
+                .filter_map(|l| l.attr("cmdprm"))
+                .filter_map(|cmd| cmd.split_whitespace().next().and_then(|h| h.strip_prefix("gohash=")).map(|h| h.to_owned()))
+                .collect();
+            let text: String = m.descendants()
+                .filter(|n| !n.ancestors().any(|a| a.value().as_element().is_some_and(|e| e.name() == "del")))
+                .filter_map(|n| n.value().as_text().map(|t| t.to_string()))
+                .collect();
+            Amendment::parse(&text, &hashes)
+        }).collect()
+    }
+    fn dom_text(p: &ElementRef) -> String
+    {
+        let mut text = String::new();
+        for child in p.children()
+        {
+            if let Some(element) = ElementRef::wrap(child)
+            {
+                if !element.value().has_class("markx", scraper::CaseSensitivity::AsciiCaseInsensitive)
+                {
+                    text.push_str(&element.text().collect::<String>());
+                }
+            }
+            else if let Some(t) = child.value().as_text()
+            {
+                text.push_str(t);
+            }
+        }
+        text.replace('\u{a0}', " ")
+    }
+
+    const REDTEXT: &str = r#"<html><head><style>p.T {font-weight: bold}</style></head><body>
+        <p class="F" id="p0">служебный</p>
+        <p class="T" id="p1">НАЛОГОВЫЙ КОДЕКС</p>
+        <p id="p2"><span class="edx">Статья 1.&nbsp;Акты</span><span class="mark">[метка]</span><label>x</label> <span class="cmd-hide" cmdprm="gohash=b113c2e0 goparaid=0 goback=0">Кодекса</span> &amp; прочее<span class="markx"> (В редакции <ins>федеральных</ins><del>Федерального</del> законов <span class="cmd" cmdprm="gohash=6cae4226 goparaid=0 goback=1">от 28.04.2023 № 1-ФЗ</span>)</span></p>
+        <p class="A" id="p3">аннотация</p>
+        <p id="p4">2. Утратил силу.<br>
+        <p id="p5">3. Третий</p>
+        </body></html>"#;
+
+    #[test]
+    fn test_stream_paragraphs()
+    {
+        let paragraphs = super::paragraphs(REDTEXT);
+        assert_eq!(paragraphs.iter().map(|p| p.id.unwrap()).collect::<Vec<usize>>(), vec![1, 2, 4, 5]);
+        let p = &paragraphs[1];
+        assert_eq!(p.text, "Статья 1. Акты Кодекса & прочее");
+        //изменивший документ акт в ссылки текста не попадает
+        assert_eq!(p.links, vec!["b113c2e0".to_owned()]);
+        assert_eq!(p.amendments.len(), 1);
+        assert_eq!(p.amendments[0].text, "В редакции федеральных законов от 28.04.2023 № 1-ФЗ");
+        assert_eq!(p.amendments[0].acts[0].hash.as_deref(), Some("6cae4226"));
+        assert!(!p.html.contains("[метка]") && !p.html.contains("edx") && !p.html.contains("&nbsp;"));
+        assert!(p.html.contains("markx") && !p.html_without_amendments.contains("markx"));
+        assert!(paragraphs[0].has_class("T"));
+        assert_eq!(paragraphs[2].text.trim(), "2. Утратил силу.");
+
+        //тот же результат что и у разбора через DOM
+        let clear = ActualRedactionsClient::clear_document_html(REDTEXT).unwrap();
+        let html = Html::parse_document(&clear);
+        let selector = Selector::parse("p#p2").unwrap();
+        let dom = html.select(&selector).next().unwrap();
+        assert_eq!(dom_text(&dom), p.text);
+        assert_eq!(dom_amendments(&dom)[0].text, p.amendments[0].text);
+    }
+}
@@ -0,0 +1,566 @@
+use std::{collections::VecDeque, fmt::{Debug, Display}, sync::{Arc, LazyLock, Mutex}};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::OnceCell;
+use tracing::info;
+use utilites::Date;
+use crate::{actual_redactions_client::{ActualRedactionsClient, RedactionTtl}, annotations::{AmendingAct, AmendmentKind}, builder, config::ClientConfig, converter::Converter, document::{DocumentNode, DocumentNodes}, error::{Error, Result}, models::{ExtendedRedaction, SystemaDocumentCard}, redline::{self, RedlineMode}, report::RedactionReport};
+
+static ADDRESS_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\b(?<unit>подпункт\w*|пп[.]?|стать\w+|статей|ст[.]?|част\w+|ч[.]?|пункт\w*|п[.]?|абзац\w*|абз[.]?|глав\w*|гл[.]?)(?:\s+|[.]\s*)(?<number>\d+(?:[.]\d+)*|[а-я](?:\b|$))").unwrap());
+///единица с перечнем номеров: "частей 2 и 3", "статей 3, 5 и 12.1", "подпункты а и б", номера в перечне одного вида
+static ADDRESS_LIST_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\b(?<unit>подпункт\w*|пп[.]?|стать\w+|статей|ст[.]?|част\w+|ч[.]?|пункт\w*|п[.]?|абзац\w*|абз[.]?|глав\w*|гл[.]?)(?:\s+|[.]\s*)(?<numbers>\d+(?:[.]\d+)*(?:(?:\s*,\s*|\s+и\s+)\d+(?:[.]\d+)*)*|[а-я](?:\b|$)(?:(?:\s*,\s*|\s+и\s+)[а-я](?:\b|$))*)").unwrap());
+static LIST_SEPARATOR_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\s*,\s*|\s+и\s+").unwrap());
+static CAPTION_NUMBER_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\$?\s*\S+\s+(?<number>\d+(?:[.]\d+)*|[а-я])").unwrap());
+static DOT_MARKER_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(?<number>\d+(?:[.]\d+)*)[.]\s").unwrap());
+static PAREN_MARKER_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(?<number>\d+(?:[.]\d+)*)[)]\s").unwrap());
+static LETTER_MARKER_RX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(?<number>[а-я])[)]\s").unwrap());
+
+///Структурная единица в адресе положения, в порядке вложенности
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AddressUnit
+{
+    Chapter,
+    Article,
+    Part,
+    Point,
+    Subpoint,
+    Paragraph
+}
+impl AddressUnit
+{
+    fn from_word(word: &str) -> Option<Self>
+    {
+        let word = word.to_lowercase();
+        if word.starts_with("подп") || word.starts_with("пп") { Some(Self::Subpoint) }
+        else if word.starts_with("гл") { Some(Self::Chapter) }
+        else if word.starts_with("ст") { Some(Self::Article) }
+        else if word.starts_with("ч") { Some(Self::Part) }
+        else if word.starts_with("абз") { Some(Self::Paragraph) }
+        else if word.starts_with("п") { Some(Self::Point) }
+        else { None }
+    }
+    ///начало `content_type` узла из содержания
+    fn content_type(&self) -> &'static str
+    {
+        match self
+        {
+            Self::Chapter => "глав",
+            Self::Article => "стать",
+            Self::Part => "част",
+            Self::Point => "пункт",
+            Self::Subpoint => "подпункт",
+            Self::Paragraph => "абзац"
+        }
+    }
+    fn name(&self) -> &'static str
+    {
+        match self
+        {
+            Self::Chapter => "глава",
+            Self::Article => "статья",
+            Self::Part => "часть",
+            Self::Point => "пункт",
+            Self::Subpoint => "подпункт",
+            Self::Paragraph => "абзац"
+        }
+    }
+}
+
+///Адрес положения документа: `статья 5 часть 2`, `ст. 5 ч. 2 п. 3`, `подпункт а пункта 3 части 2 статьи 5`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Address
+{
+    ///единицы от главы или статьи к абзацу
+    pub units: Vec<(AddressUnit, String)>
+}
+impl Address
+{
+    pub fn parse(address: &str) -> Option<Self>
+    {
+        let mut units: Vec<(AddressUnit, String)> = ADDRESS_RX.captures_iter(address)
+            .filter_map(|c| Some((AddressUnit::from_word(c.name("unit")?.as_str())?, c.name("number")?.as_str().to_lowercase())))
+            .collect();
+        units.sort_by_key(|(unit, _)| *unit);
+        units.dedup_by_key(|(unit, _)| *unit);
+        if units.is_empty() { None } else { Some(Self { units }) }
+    }
+    ///Все адреса из перечня: "пункта 3 части 1, частей 2 и 4 статьи 5, статей 7 и 12.1" ->
+    /// `статья 5 часть 1 пункт 3`, `статья 5 часть 2`, `статья 5 часть 4`, `статья 7`, `статья 12.1`
+    /// нижестоящие единицы идут раньше своей статьи, поэтому ждут ее и получают ее номер
+    pub fn parse_list(text: &str) -> Vec<Self>
+    {
+        let mut addresses: Vec<Self> = Vec::new();
+        let mut pending: Vec<Self> = Vec::new();
+        for c in ADDRESS_LIST_RX.captures_iter(text)
+        {
+            let (Some(unit), Some(numbers)) = (c.name("unit").and_then(|u| AddressUnit::from_word(u.as_str())), c.name("numbers")) else
+            {
+                continue;
+            };
+            let numbers: Vec<String> = LIST_SEPARATOR_RX.split(numbers.as_str()).map(|n| n.trim().to_lowercase()).filter(|n| !n.is_empty()).collect();
+            //единица с одним номером уточняет все ждущие адреса уровнем ниже: "пункты 1 и 2 части 3"
+            let is_child = |a: &Self| a.units.first().is_some_and(|(u, _)| unit < *u);
+            if numbers.len() == 1 && pending.iter().any(is_child)
+            {
+                for address in pending.iter_mut().filter(|a| is_child(a))
+                {
+                    address.units.insert(0, (unit, numbers[0].clone()));
+                }
+            }
+            else
+            {
+                pending.extend(numbers.into_iter().map(|n| Self { units: vec![(unit, n)] }));
+            }
+            //статьи нумеруются по всему документу, дальше адрес не уточняется
+            if unit <= AddressUnit::Article
+            {
+                addresses.append(&mut pending);
+            }
+        }
+        addresses.append(&mut pending);
+        addresses
+    }
+}
+impl Display for Address
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        let address: Vec<String> = self.units.iter().map(|(unit, number)| [unit.name(), " ", number].concat()).collect();
+        f.write_str(&address.join(" "))
+    }
+}
+
+///Текст положения на дату
+#[derive(Debug, Serialize)]
+pub struct ProvisionAt
+{
+    pub address: Address,
+    ///заголовок узла, для частей и пунктов без своего узла в содержании - заголовок статьи
+    pub caption: String,
+    pub text: String,
+    ///редакция действовавшая на дату
+    pub redaction: ExtendedRedaction,
+    ///дата следующего изменения текста документа, `None` если после даты изменений нет
+    pub next_change: Option<Date>,
+    pub repealed: bool,
+    ///ссылка на положение в редакции на портале
+    pub url: String
+}
+
+///Изменение положения документа
+#[derive(Debug, Serialize, Clone)]
+pub struct AmendmentEvent
+{
+    pub kind: AmendmentKind,
+    ///документ который внес изменение
+    pub act: AmendingAct,
+    ///первая редакция с этим изменением, `None` если в списке редакций документа такого изменения нет
+    pub redaction_id: Option<u32>,
+    ///дата вступления изменения в силу
+    pub effective_from: Option<Date>,
+    ///текст аннотации
+    pub annotation: String
+}
+
+///Сколько деревьев редакций держим в памяти по умолчанию
+const TREES_CACHE_SIZE: usize = 8;
+
+///Кеш деревьев по id редакции: хранятся только `capacity` последних использованных,
+/// редакция загружается один раз даже если ее запросили параллельно
+struct TreeCache<T>
+{
+    capacity: usize,
+    ///недавно использованные в конце очереди
+    entries: Mutex<VecDeque<(u32, Arc<OnceCell<Arc<T>>>)>>
+}
+impl<T> TreeCache<T>
+{
+    fn new(capacity: usize) -> Self
+    {
+        Self { capacity: capacity.max(1), entries: Mutex::new(VecDeque::new()) }
+    }
+    fn cell(&self, id: u32) -> Arc<OnceCell<Arc<T>>>
+    {
+        let mut entries = self.entries.lock().unwrap();
+        let cell = match entries.iter().position(|(i, _)| *i == id)
+        {
+            Some(position) => entries.remove(position).unwrap().1,
+            None => Arc::new(OnceCell::new())
+        };
+        entries.push_back((id, cell.clone()));
+        while entries.len() > self.capacity
+        {
+            entries.pop_front();
+        }
+        cell
+    }
+    ///Значение из кеша или результат `load`, пока идет загрузка остальные запросы той же редакции ее ждут
+    /// при ошибке в кеше ничего не остается и следующий запрос загружает заново
+    async fn get_or_load(&self, id: u32, load: impl Future<Output = Result<T>>) -> Result<Arc<T>>
+    {
+        self.cell(id).get_or_try_init(|| async { load.await.map(Arc::new) }).await.cloned()
+    }
+    fn len(&self) -> usize
+    {
+        self.entries.lock().unwrap().len()
+    }
+}
+
+///Запросы к тексту документа на дату, деревья узлов кешируются по редакциям
+pub struct ProvisionLookup<CONV, CONT>
+where   CONT: ToString + Debug,
+        CONV: Converter<CONT>
+{
+    client: ActualRedactionsClient,
+    converter: CONV,
+    trees: TreeCache<DocumentNodes<CONT>>
+}
+
+impl<CONV, CONT> ProvisionLookup<CONV, CONT>
+where   CONT: ToString + Debug,
+        CONV: Converter<CONT>
+{
+    pub fn new(config: ClientConfig, converter: CONV) -> Self
+    {
+        Self { client: ActualRedactionsClient::new(config), converter, trees: TreeCache::new(TREES_CACHE_SIZE) }
+    }
+    ///Сколько деревьев редакций держать в памяти, по умолчанию 8
+    pub fn with_cache_size(mut self, size: usize) -> Self
+    {
+        self.trees = TreeCache::new(size);
+        self
+    }
+    ///Текст `статья 5 часть 2` в редакции действовавшей на `date`
+    pub async fn article_at(&self, document: &SystemaDocumentCard, address: &str, date: &Date) -> Result<ProvisionAt>
+    {
+        let address = parse_address(address)?;
+        let redactions = self.client.get_redactions_by_hash(&document.hash, RedactionTtl::Actual).await?;
+        let redaction = redactions.in_force_on(date)
+            .ok_or(Error::ContentError(["На ", &date.to_string(), " у документа ", &document.number, " нет действующей редакции"].concat()))?
+            .clone();
+        let next_change = redactions.next_change_after(date).cloned();
+        let nodes = self.tree(document, &redaction).await?;
+        let provision = find_provision(&nodes, &address).ok_or(not_found(&address, &redaction))?;
+        Ok(ProvisionAt
+        {
+            caption: provision[0].caption().trim_start_matches('$').to_owned(),
+            text: provision.iter().map(|n| n.text_with_notes()).collect::<Vec<String>>().join("\n"),
+            repealed: provision.iter().any(|n| nodes.is_node_repealed(n)),
+            url: nodes.node_url(provision[0]),
+            address,
+            redaction,
+            next_change
+        })
+    }
+    ///Все изменения положения: каким документом, когда и как оно менялось  
+    /// аннотации берутся из последней редакции, даты вступления в силу - из списка редакций
+    pub async fn amendment_history(&self, document: &SystemaDocumentCard, address: &str) -> Result<Vec<AmendmentEvent>>
+    {
+        let address = parse_address(address)?;
+        let redactions = self.client.get_redactions_by_hash(&document.hash, RedactionTtl::Actual).await?;
+        let redaction = redactions.last()
+            .ok_or(Error::ContentError(["У документа ", &document.number, " нет редакций"].concat()))?;
+        let nodes = self.tree(document, redaction).await?;
+        let provision = find_provision(&nodes, &address).ok_or(not_found(&address, redaction))?;
+        Ok(amendment_events(&provision, &redactions))
+    }
+    ///Сводки изменений по каждой редакции документа вышедшей после `since`, редакция сравнивается с предыдущей
+    pub async fn redaction_reports(&self, document: &SystemaDocumentCard, since: &Date) -> Result<Vec<RedactionReport>>
+    {
+        let redactions = self.client.get_redactions_by_hash(&document.hash, RedactionTtl::Actual).await?;
+        let mut reports = Vec::new();
+        for pair in redactions.windows(2).filter(|w| &w[1].date > since)
+        {
+            let previous = self.tree(document, &pair[0]).await?;
+            let current = self.tree(document, &pair[1]).await?;
+            reports.push(RedactionReport::compare(&previous, &current, pair[1].clone()));
+        }
+        info!("по документу {} с {} новых редакций: {}", document.number, since, reports.len());
+        Ok(reports)
+    }
+    ///Html сравнение двух редакций документа по их id (`render_redline`)
+    pub async fn redline(&self, document: &SystemaDocumentCard, previous_id: u32, current_id: u32, mode: RedlineMode) -> Result<String>
+    {
+        let redactions = self.client.get_redactions_by_hash(&document.hash, RedactionTtl::Actual).await?;
+        let find = |id: u32| redactions.iter().find(|r| r.id == id)
+            .ok_or(Error::ContentError(["У документа ", &document.number, " нет редакции ", &id.to_string()].concat()));
+        let previous = self.tree(document, find(previous_id)?).await?;
+        let current = self.tree(document, find(current_id)?).await?;
+        Ok(redline::render_redline(&previous, &current, mode))
+    }
+    ///Дерево узлов редакции, при повторных запросах берется из кеша
+    pub async fn tree(&self, document: &SystemaDocumentCard, redaction: &ExtendedRedaction) -> Result<Arc<DocumentNodes<CONT>>>
+    {
+        self.trees.get_or_load(redaction.id, async
+        {
+            info!("получаем редакцию {} документа {}", redaction.id, document.number);
+            let response = self.client.get_document_redaction(document, redaction).await?;
+            builder::build_nodes(response, &self.converter)
+        }).await
+    }
+}
+
+fn parse_address(address: &str) -> Result<Address>
+{
+    Address::parse(address).ok_or(Error::ContentError(["Не удалось разобрать адрес положения `", address, "`"].concat()))
+}
+
+fn not_found(address: &Address, redaction: &ExtendedRedaction) -> Error
+{
+    Error::ContentError([&address.to_string(), " не найден(а) в редакции ", &redaction.id.to_string()].concat())
+}
+
+///События изменения по аннотациям узлов положения, каждый документ упоминается один раз для каждого вида изменения  
+/// редакция изменения ищется по номеру и дате изменяющего документа (`source_number`, `source_date`)
+pub(crate) fn amendment_events<C: ToString + Debug>(provision: &[&DocumentNode<C>], redactions: &[ExtendedRedaction]) -> Vec<AmendmentEvent>
+{
+    let mut events: Vec<AmendmentEvent> = Vec::new();
+    for amendment in provision.iter().flat_map(|n| n.amendments())
+    {
+        for act in &amendment.acts
+        {
+            if events.iter().any(|e| e.kind == amendment.kind && e.act.number == act.number && e.act.date == act.date)
+            {
+                continue;
+            }
+            let redaction = act.number.as_ref().and_then(|number| redactions.iter().find(|r|
+                r.source_number.as_ref() == Some(number)
+                && (r.source_date.is_none() || act.date.is_none() || r.source_date == act.date)));
+            events.push(AmendmentEvent
+            {
+                kind: amendment.kind,
+                act: act.clone(),
+                redaction_id: redaction.map(|r| r.id),
+                effective_from: redaction.and_then(|r| r.effective_from.clone()),
+                annotation: amendment.text.clone()
+            });
+        }
+    }
+    events.sort_by(|a, b| a.act.date.cmp(&b.act.date).then(a.effective_from.cmp(&b.effective_from)));
+    events
+}
+
+fn caption_number(caption: &str) -> Option<String>
+{
+    CAPTION_NUMBER_RX.captures(caption.trim()).and_then(|c| c.name("number")).map(|n| n.as_str().to_lowercase())
+}
+
+///Текст узла без начальной разметки (`**`, `#` и т.п. после конвертера)
+fn node_text<C: ToString + Debug>(node: &DocumentNode<C>) -> String
+{
+    node.converted_content().to_string().trim_start_matches(|c: char| !c.is_alphanumeric()).to_owned()
+}
+
+fn marker(rx: &Regex, text: &str) -> Option<String>
+{
+    rx.captures(text).and_then(|c| c.name("number")).map(|n| n.as_str().to_owned())
+}
+
+///Узел и все его потомки в порядке документа
+fn subtree<C: ToString + Debug>(nodes: &DocumentNodes<C>, idx: usize, out: &mut Vec<usize>)
+{
+    out.push(idx);
+    for child in nodes.get_children(idx)
+    {
+        subtree(nodes, *child, out);
+    }
+}
+
+///Узлы положения по адресу, первый узел - заголовок или первый абзац положения
+/// сначала ищем по содержанию, если в содержании частей и пунктов нет - по нумерации абзацев `1.`, `1)`, `а)`
+pub(crate) fn find_provision<'a, C: ToString + Debug>(nodes: &'a DocumentNodes<C>, address: &Address) -> Option<Vec<&'a DocumentNode<C>>>
+{
+    let (first_unit, first_number) = address.units.first()?;
+    let root = nodes.into_iter().position(|n| n.content_type().to_lowercase().starts_with(first_unit.content_type()) && caption_number(n.caption()).as_ref() == Some(first_number))?;
+    let mut items = Vec::new();
+    subtree(nodes, root, &mut items);
+    items.sort_by_key(|i| nodes.get_node(*i).map(|n| n.start_id()));
+    //заголовок статьи в абзацы не входит
+    let mut has_heading = true;
+    let mut parent_unit = *first_unit;
+    for (unit, number) in &address.units[1..]
+    {
+        let children = if has_heading { &items[1..] } else { &items[..] };
+        let by_contents = children.iter()
+            .position(|i| nodes.get_node(*i).is_some_and(|n| n.content_type().to_lowercase().starts_with(unit.content_type()) && caption_number(n.caption()).as_ref() == Some(number)));
+        items = if let Some(pos) = by_contents
+        {
+            let mut sub = Vec::new();
+            subtree(nodes, children[pos], &mut sub);
+            sub.sort_by_key(|i| nodes.get_node(*i).map(|n| n.start_id()));
+            has_heading = true;
+            sub
+        }
+        else
+        {
+            let texts: Vec<String> = children.iter().filter_map(|i| nodes.get_node(*i)).map(node_text).collect();
+            let range = match unit
+            {
+                AddressUnit::Paragraph =>
+                {
+                    let n: usize = number.parse().ok()?;
+                    (n > 0 && n <= texts.len()).then(|| (n - 1, n))
+                }
+                AddressUnit::Part => marker_range(&texts, &DOT_MARKER_RX, number),
+                //в кодексах статьи делятся на пункты `1.`, в законах пункты `1)` внутри частей
+                AddressUnit::Point if parent_unit == AddressUnit::Article => marker_range(&texts, &DOT_MARKER_RX, number).or_else(|| marker_range(&texts, &PAREN_MARKER_RX, number)),
+                AddressUnit::Point => marker_range(&texts, &PAREN_MARKER_RX, number),
+                AddressUnit::Subpoint if number.chars().all(|c| c.is_ascii_digit()) => marker_range(&texts, &PAREN_MARKER_RX, number),
+                AddressUnit::Subpoint => marker_range(&texts, &LETTER_MARKER_RX, number),
+                AddressUnit::Chapter | AddressUnit::Article => None
+            }?;
+            has_heading = false;
+            children[range.0..range.1].to_vec()
+        };
+        parent_unit = *unit;
+    }
+    Some(items.into_iter().filter_map(|i| nodes.get_node(i)).collect())
+}
+
+///Диапазон абзацев от абзаца с номером `number` до следующего абзаца с такой же нумерацией
+fn marker_range(texts: &[String], rx: &Regex, number: &str) -> Option<(usize, usize)>
+{
+    let start = texts.iter().position(|t| marker(rx, t).as_deref() == Some(number))?;
+    let end = texts[start + 1..].iter().position(|t| marker(rx, t).is_some()).map(|p| start + 1 + p).unwrap_or(texts.len());
+    Some((start, end))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use utilites::Date;
+    use crate::{actual_redactions_client::ActualRedactionsClient, annotations::{Amendment, AmendmentKind}, config::ClientConfig, converter, document::{DocumentNode, DocumentNodes}, logger, models::{DocumentState, ExtendedRedaction, RedactionStatus, RedactionType}};
+    use super::{Address, AddressUnit, ProvisionLookup, TreeCache};
+
+    struct NotConvert;
+    impl converter::Converter<String> for NotConvert
+    {
+        fn convert(&self, html: String) -> String
+        {
+            html
+        }
+    }
+
+    #[test]
+    fn test_parse_address()
+    {
+        let address = Address::parse("статья 5 часть 2").unwrap();
+        assert_eq!(address.units, vec![(AddressUnit::Article, "5".to_owned()), (AddressUnit::Part, "2".to_owned())]);
+        let address = Address::parse("подпункт а пункта 3 части 2 статьи 12.1").unwrap();
+        assert_eq!(address.to_string(), "статья 12.1 часть 2 пункт 3 подпункт а");
+        let address = Address::parse("ст. 5 ч. 2 п. 3").unwrap();
+        assert_eq!(address.units.len(), 3);
+        assert!(Address::parse("преамбула").is_none());
+        let list: Vec<String> = Address::parse_list("подпункт а пункта 3 части 1, части 2 и 4 статьи 5, статей 7 и 12.1, главы 2")
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+        assert_eq!(list, ["статья 5 часть 1 пункт 3 подпункт а", "статья 5 часть 2", "статья 5 часть 4", "статья 7", "статья 12.1", "глава 2"]);
+    }
+
+    #[test]
+    fn test_find_provision()
+    {
+        let mut nodes: DocumentNodes<String> = DocumentNodes::default();
+        nodes.insert(DocumentNode::new("статья", String::new(), "Статья 4. Общие положения".to_owned(), None, 1, 9, 0, "$Статья 4"));
+        nodes.insert(DocumentNode::new("статья", String::new(), "Статья 5. Права".to_owned(), None, 10, 15, 0, "$Статья 5"));
+        let paragraphs = ["1. Первая часть.", "2. Вторая часть:", "1) пункт один;", "2) пункт два.", "3. Третья часть."];
+        for (i, text) in paragraphs.iter().enumerate()
+        {
+            nodes.insert(DocumentNode::new("параграф", String::new(), text.to_string(), None, 11 + i, 11 + i, 1, "параграф"));
+        }
+        let texts = |address: &str| super::find_provision(&nodes, &Address::parse(address).unwrap())
+            .map(|p| p.iter().map(|n| n.converted_content().clone()).collect::<Vec<String>>());
+        assert_eq!(texts("статья 5").unwrap().len(), 6);
+        assert_eq!(texts("статья 5 часть 2").unwrap(), vec!["2. Вторая часть:", "1) пункт один;", "2) пункт два."]);
+        assert_eq!(texts("пункт 2 части 2 статьи 5").unwrap(), vec!["2) пункт два."]);
+        assert_eq!(texts("статья 5 часть 2 абзац 1").unwrap(), vec!["2. Вторая часть:"]);
+        assert!(texts("статья 5 часть 4").is_none());
+        assert!(texts("статья 6").is_none());
+    }
+
+    fn redaction(id: u32, date: Date, source_number: &str, source_date: Date) -> ExtendedRedaction
+    {
+        ExtendedRedaction
+        {
+            id,
+            date: date.clone(),
+            state_id: 1,
+            state: DocumentState::InForceWithChanges,
+            elements: 0,
+            redaction_type: RedactionType::Scheduled,
+            caption: String::new(),
+            status: RedactionStatus::Inactive,
+            flag: 0,
+            is_actual: false,
+            source_number: Some(source_number.to_owned()),
+            source_date: Some(source_date),
+            effective_from: Some(date),
+            effective_to: None
+        }
+    }
+
+    #[test]
+    fn test_amendment_events()
+    {
+        let article = DocumentNode::new("статья", String::new(), "Статья 5. Права".to_owned(), None, 10, 15, 0, "$Статья 5")
+            .with_amendments(vec![Amendment::parse("(В редакции федеральных законов от 29.12.2017 № 473-ФЗ; от 26.07.2019 № 232-ФЗ)", &[])], false);
+        let part = DocumentNode::new("параграф", String::new(), "3. Третья часть.".to_owned(), None, 12, 12, 1, "параграф")
+            .with_amendments(vec![
+                Amendment::parse("(Дополнение частью - Федеральный закон от 26.07.2019 № 232-ФЗ)", &[]),
+                Amendment::parse("(В редакции Федерального закона от 26.07.2019 № 232-ФЗ)", &[])], false);
+        let redactions = vec![
+            redaction(10, Date::new_date(1, 1, 2018), "473-ФЗ", Date::new_date(29, 12, 2017)),
+            redaction(11, Date::new_date(1, 9, 2019), "232-ФЗ", Date::new_date(26, 7, 2019))];
+        let events = super::amendment_events(&[&article, &part], &redactions);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].act.number.as_deref(), Some("473-ФЗ"));
+        assert_eq!(events[0].redaction_id, Some(10));
+        assert_eq!(events[0].effective_from, Some(Date::new_date(1, 1, 2018)));
+        assert!(events[1..].iter().all(|e| e.redaction_id == Some(11)));
+        assert!(events.iter().any(|e| e.kind == AmendmentKind::Addition));
+    }
+
+    #[tokio::test]
+    async fn test_tree_cache()
+    {
+        let cache = TreeCache::new(2);
+        let loads = &std::sync::atomic::AtomicUsize::new(0);
+        let load = |value: u32| async move
+        {
+            loads.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            tokio::task::yield_now().await;
+            Ok(value)
+        };
+        //параллельные запросы одной редакции - одна загрузка
+        let (a, b) = tokio::join!(cache.get_or_load(1, load(10)), cache.get_or_load(1, load(10)));
+        assert_eq!((*a.unwrap(), *b.unwrap()), (10, 10));
+        assert_eq!(loads.load(std::sync::atomic::Ordering::SeqCst), 1);
+        cache.get_or_load(2, load(20)).await.unwrap();
+        cache.get_or_load(1, load(10)).await.unwrap();
+        //вытесняется давно не использованная 2, а не 1
+        cache.get_or_load(3, load(30)).await.unwrap();
+        assert_eq!(cache.len(), 2);
+        cache.get_or_load(1, load(10)).await.unwrap();
+        assert_eq!(loads.load(std::sync::atomic::Ordering::SeqCst), 3);
+        cache.get_or_load(2, load(20)).await.unwrap();
+        assert_eq!(loads.load(std::sync::atomic::Ordering::SeqCst), 4);
+        assert!(cache.get_or_load(4, async { Err(crate::error::Error::ContentError("нет".to_owned())) }).await.is_err());
+        assert_eq!(*cache.get_or_load(4, load(40)).await.unwrap(), 40);
+    }
+
+    #[tokio::test]
+    async fn test_article_at()
+    {
+        logger::init();
+        let card = ActualRedactionsClient::default().search_default(Date::new_date(29, 12, 2012), "273-ФЗ").await.unwrap();
+        let lookup = ProvisionLookup::new(ClientConfig::default(), NotConvert);
+        let date = Date::new_date(1, 9, 2020);
+        let provision = lookup.article_at(&card, "статья 5 часть 2", &date).await.unwrap();
+        tracing::info!("{} ({}), редакция {}, следующее изменение {:?}:\n{}", provision.address, provision.caption, provision.redaction.id, provision.next_change, provision.text);
+        assert!(provision.redaction.is_in_force_on(&date));
+        assert!(!provision.text.is_empty());
+        lookup.article_at(&card, "статья 5 часть 3", &date).await.unwrap();
+        assert_eq!(lookup.trees.len(), 1);
+    }
+}
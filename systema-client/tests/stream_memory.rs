@@ -0,0 +1,156 @@
+//! Сравнение памяти и времени построения дерева узлов (`LegalSource::get_document`, потоковый разбор)
+//! и разбора через DOM на документе размером с кодекс.
+//! Отдельный тестовый бинарник, чтобы считающий аллокатор не подменял аллокатор остальных тестов.
+//! `cargo test --release -p systema-client --test stream_memory -- --ignored --nocapture`
+use std::{alloc::{GlobalAlloc, Layout, System}, cell::RefCell, sync::atomic::{AtomicUsize, Ordering}, time::{Duration, Instant}};
+use scraper::{ElementRef, Html, Selector};
+use systema_client::{ActualRedactionsClient, Amendment, Contents, Converter, DocumentResponse, DocumentState, Error, LegalSource};
+use utilites::Date;
+
+///считаем пиковое потребление памяти
+struct CountingAlloc;
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+unsafe impl GlobalAlloc for CountingAlloc
+{
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8
+    {
+        let current = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(current, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout)
+    {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+///пик памяти при выполнении `f` сверх уже выделенного и время выполнения
+fn measure<T>(f: impl FnOnce() -> T) -> (T, usize, Duration)
+{
+    let base = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(base, Ordering::Relaxed);
+    let start = Instant::now();
+    let result = f();
+    (result, PEAK.load(Ordering::Relaxed) - base, start.elapsed())
+}
+
+///html параграфа как есть, конвертация в тест не входит
+struct Html2Html;
+impl Converter<String> for Html2Html
+{
+    fn convert(&self, html: String) -> String
+    {
+        html
+    }
+}
+
+///Отдает заранее полученную редакцию, дальше она строится как любой документ из источника
+struct Prepared(RefCell<Option<DocumentResponse>>);
+impl LegalSource for Prepared
+{
+    type Id = ();
+    async fn fetch(&self, _id: &Self::Id) -> Result<DocumentResponse, Error>
+    {
+        Ok(self.0.borrow_mut().take().unwrap())
+    }
+}
+
+//разбор параграфа через DOM как было до потокового разбора
+fn dom_amendments(p: &ElementRef) -> Vec<Amendment>
+{
+    let markx_selector = Selector::parse("span.markx").unwrap();
+    let links_selector = Selector::parse("span[cmdprm]").unwrap();
+    p.select(&markx_selector).map(|m|
+    {
+        let hashes: Vec<String> = m.select(&links_selector)
+            .filter_map(|l| l.attr("cmdprm"))
+            .filter_map(|cmd| cmd.split_whitespace().next().and_then(|h| h.strip_prefix("gohash=")).map(|h| h.to_owned()))
+            .collect();
+        let text: String = m.descendants()
+            .filter(|n| !n.ancestors().any(|a| a.value().as_element().is_some_and(|e| e.name() == "del")))
+            .filter_map(|n| n.value().as_text().map(|t| t.to_string()))
+            .collect();
+        Amendment::parse(&text, &hashes)
+    }).collect()
+}
+fn dom_without_amendments(p: &ElementRef) -> String
+{
+    let markx_selector = Selector::parse("span.markx").unwrap();
+    let mut html = p.html();
+    for m in p.select(&markx_selector)
+    {
+        html = html.replace(&m.html(), "");
+    }
+    html
+}
+fn dom_text(p: &ElementRef) -> String
+{
+    let mut text = String::new();
+    for child in p.children()
+    {
+        if let Some(element) = ElementRef::wrap(child)
+        {
+            if !element.value().has_class("markx", scraper::CaseSensitivity::AsciiCaseInsensitive)
+            {
+                text.push_str(&element.text().collect::<String>());
+            }
+        }
+        else if let Some(t) = child.value().as_text()
+        {
+            text.push_str(t);
+        }
+    }
+    text.replace('\u{a0}', " ")
+}
+
+#[test]
+#[ignore]
+fn stream_vs_dom_memory()
+{
+    let paragraph = r#"<p id="p{id}" class="ed"><span class="edx">{id}. Налогоплательщики&nbsp;обязаны уплачивать законно установленные налоги, <span class="cmd-hide" cmdprm="gohash=b113c2e08341853ef53a8dad4585b513d96f85e0f3d0d246a25ecf52e40608db goparaid=0 goback=0">Налогового кодекса Российской Федерации</span></span><span class="mark">[{id}]</span><span class="markx"> (В редакции Федерального закона <span class="cmd" cmdprm="gohash=6cae422689d86593934b89f35c4515536bb9820c1e6c39dd4661577ae5149180 goparaid=0 goback=1">от 28.04.2023 № 1-ФЗ</span>)</span></p>
+"#;
+    let mut redtext = String::from("<html><head></head><body>");
+    for id in 0..60_000
+    {
+        redtext.push_str(&paragraph.replace("{id}", &id.to_string()));
+    }
+    redtext.push_str("</body></html>");
+
+    let document = DocumentResponse
+    {
+        html: redtext.clone(),
+        contents: Contents::default(),
+        name: "Налоговый кодекс".to_owned(),
+        number: "146-ФЗ".to_owned(),
+        sign_date: Date::new_date(31, 7, 1998),
+        publication_url: String::new(),
+        state: DocumentState::InForce,
+        hash: "b113c2e08341853ef53a8dad4585b513d96f85e0f3d0d246a25ecf52e40608db".to_owned(),
+        redaction_id: 1
+    };
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    //так строились узлы до потокового разбора: DOM очищенного документа и все параграфы разом
+    let (dom_paragraphs, dom_peak, dom_time) = measure(||
+    {
+        let clear = ActualRedactionsClient::clear_document_html(&redtext).unwrap();
+        let html = Html::parse_document(&clear);
+        let selector = Selector::parse("p:not(.mark):not(.markx)").unwrap();
+        html.select(&selector)
+            .map(|p| (p.html(), Html2Html.convert(dom_without_amendments(&p)), dom_text(&p), dom_amendments(&p)))
+            .collect::<Vec<_>>()
+    });
+    let dom_count = dom_paragraphs.len();
+    drop(dom_paragraphs);
+    let source = Prepared(RefCell::new(Some(document)));
+    let (nodes, stream_peak, stream_time) = measure(|| runtime.block_on(source.get_document(&(), &Html2Html)).unwrap());
+    println!("документ {} Мб, {} параграфов, {} узлов", redtext.len() / 1024 / 1024, dom_count, nodes.node_count());
+    println!("DOM: пик {} Мб, {:?}", dom_peak / 1024 / 1024, dom_time);
+    println!("build_nodes: пик {} Мб, {:?}", stream_peak / 1024 / 1024, stream_time);
+    assert!(nodes.node_count() >= dom_count);
+    assert!(stream_peak * 2 < dom_peak);
+    assert!(stream_time < dom_time);
+}